@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE fenster.public.users
+    ADD COLUMN IF NOT EXISTS user_editor BOOL DEFAULT FALSE;
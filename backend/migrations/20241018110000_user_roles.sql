@@ -0,0 +1,16 @@
+-- Add migration script here
+CREATE TYPE user_role AS ENUM ('reader', 'author', 'editor', 'admin');
+
+ALTER TABLE fenster.public.users
+    ADD COLUMN user_role user_role NOT NULL DEFAULT 'reader';
+
+UPDATE fenster.public.users
+SET user_role = CASE
+                    WHEN user_editor THEN 'editor'::user_role
+                    WHEN user_author THEN 'author'::user_role
+                    ELSE 'reader'::user_role
+    END;
+
+ALTER TABLE fenster.public.users
+    DROP COLUMN user_author,
+    DROP COLUMN user_editor;
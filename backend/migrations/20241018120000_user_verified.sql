@@ -0,0 +1,6 @@
+-- Add migration script here
+ALTER TABLE fenster.public.users
+    ADD COLUMN user_verified BOOL NOT NULL DEFAULT FALSE;
+
+UPDATE fenster.public.users
+SET user_verified = TRUE;
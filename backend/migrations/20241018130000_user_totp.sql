@@ -0,0 +1,12 @@
+-- Add migration script here
+ALTER TABLE fenster.public.users
+    ADD COLUMN user_totp_secret  TEXT,
+    ADD COLUMN user_totp_enabled BOOL NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS fenster.public.user_recovery_codes
+(
+    user_id   TEXT NOT NULL REFERENCES fenster.public.users ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    CONSTRAINT user_recovery_codes_pk
+        PRIMARY KEY (user_id, code_hash)
+);
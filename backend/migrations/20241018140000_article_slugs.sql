@@ -0,0 +1,23 @@
+-- Add migration script here
+ALTER TABLE fenster.public.articles
+    DROP CONSTRAINT articles_pk;
+
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_id BIGSERIAL
+        CONSTRAINT articles_pk
+            PRIMARY KEY;
+
+ALTER TABLE fenster.public.articles
+    ADD CONSTRAINT articles_slug_unique
+        UNIQUE (article_slug);
+
+CREATE TABLE IF NOT EXISTS fenster.public.article_slugs
+(
+    slug       TEXT      NOT NULL
+        CONSTRAINT article_slugs_pk
+            PRIMARY KEY,
+    article_id BIGINT    NOT NULL REFERENCES fenster.public.articles ON DELETE CASCADE,
+    retired_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS article_slugs_article_idx ON fenster.public.article_slugs (article_id);
\ No newline at end of file
@@ -0,0 +1,33 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS fenster.public.article_revisions
+(
+    revision_id      BIGSERIAL
+        CONSTRAINT article_revisions_pk
+            PRIMARY KEY,
+    article_id       BIGINT    NOT NULL REFERENCES fenster.public.articles ON DELETE CASCADE,
+    revision_number  INT       NOT NULL,
+    revision_author  TEXT      REFERENCES fenster.public.users ON DELETE SET NULL,
+    revision_title   TEXT      NOT NULL,
+    revision_content TEXT      NOT NULL,
+    creation_date    TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT article_revisions_number_unique
+        UNIQUE (article_id, revision_number)
+);
+
+CREATE OR REPLACE FUNCTION fenster.public.article_revisions_immutable() RETURNS TRIGGER AS
+$$
+BEGIN
+    RAISE EXCEPTION 'article revisions are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER article_revisions_immutable
+    BEFORE UPDATE OF article_id, revision_number, revision_title, revision_content, creation_date
+    ON fenster.public.article_revisions
+    FOR EACH ROW
+EXECUTE FUNCTION fenster.public.article_revisions_immutable();
+
+INSERT INTO fenster.public.article_revisions
+    (article_id, revision_number, revision_author, revision_title, revision_content, creation_date)
+SELECT article_id, 1, article_author, article_title, article_content, editing_date
+FROM fenster.public.articles;
\ No newline at end of file
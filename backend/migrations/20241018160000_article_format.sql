@@ -0,0 +1,21 @@
+-- Add migration script here
+CREATE TYPE content_format AS ENUM ('plain', 'markdown', 'html');
+
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_format content_format NOT NULL DEFAULT 'markdown';
+
+ALTER TABLE fenster.public.article_revisions
+    ADD COLUMN revision_format       content_format NOT NULL DEFAULT 'markdown',
+    ADD COLUMN revision_content_html TEXT;
+
+UPDATE fenster.public.articles
+SET article_format = 'plain';
+
+UPDATE fenster.public.article_revisions
+SET revision_format = 'plain';
+
+CREATE OR REPLACE TRIGGER article_revisions_immutable
+    BEFORE UPDATE OF article_id, revision_number, revision_title, revision_content, revision_format, creation_date
+    ON fenster.public.article_revisions
+    FOR EACH ROW
+EXECUTE FUNCTION fenster.public.article_revisions_immutable();
\ No newline at end of file
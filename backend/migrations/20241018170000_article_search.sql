@@ -0,0 +1,10 @@
+-- Add migration script here
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_search TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('german', article_title), 'A') ||
+        setweight(to_tsvector('english', article_title), 'A') ||
+        setweight(to_tsvector('german', article_content), 'B') ||
+        setweight(to_tsvector('english', article_content), 'B')
+        ) STORED;
+
+CREATE INDEX IF NOT EXISTS articles_search_idx ON fenster.public.articles USING GIN (article_search);
\ No newline at end of file
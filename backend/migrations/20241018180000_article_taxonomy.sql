@@ -0,0 +1,43 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS fenster.public.categories
+(
+    category_id   BIGSERIAL
+        CONSTRAINT categories_pk
+            PRIMARY KEY,
+    category_slug TEXT NOT NULL
+        CONSTRAINT categories_slug_unique
+            UNIQUE,
+    category_name TEXT NOT NULL
+);
+
+INSERT INTO fenster.public.categories (category_slug, category_name)
+VALUES ('news', 'News'),
+       ('sports', 'Sports'),
+       ('culture', 'Culture'),
+       ('opinion', 'Opinion');
+
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_category BIGINT REFERENCES fenster.public.categories ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS articles_category_idx ON fenster.public.articles (article_category);
+
+CREATE TABLE IF NOT EXISTS fenster.public.tags
+(
+    tag_id   BIGSERIAL
+        CONSTRAINT tags_pk
+            PRIMARY KEY,
+    tag_slug TEXT NOT NULL
+        CONSTRAINT tags_slug_unique
+            UNIQUE,
+    tag_name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS fenster.public.article_tags
+(
+    article_id BIGINT NOT NULL REFERENCES fenster.public.articles ON DELETE CASCADE,
+    tag_id     BIGINT NOT NULL REFERENCES fenster.public.tags ON DELETE CASCADE,
+    CONSTRAINT article_tags_pk
+        PRIMARY KEY (article_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS article_tags_tag_idx ON fenster.public.article_tags (tag_id);
\ No newline at end of file
@@ -0,0 +1,23 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS fenster.public.issues
+(
+    issue_number       INT       NOT NULL
+        CONSTRAINT issues_pk
+            PRIMARY KEY,
+    issue_title        TEXT      NOT NULL,
+    issue_cover_image  TEXT,
+    issue_release_date DATE      NOT NULL,
+    issue_published    BOOL      NOT NULL DEFAULT FALSE,
+    creation_date      TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS fenster.public.issue_articles
+(
+    issue_number INT    NOT NULL REFERENCES fenster.public.issues ON DELETE CASCADE,
+    article_id   BIGINT NOT NULL REFERENCES fenster.public.articles ON DELETE CASCADE
+        CONSTRAINT issue_articles_article_unique
+            UNIQUE,
+    position     INT    NOT NULL,
+    CONSTRAINT issue_articles_pk
+        PRIMARY KEY (issue_number, article_id)
+);
\ No newline at end of file
@@ -0,0 +1,12 @@
+-- Add migration script here
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_publish_at TIMESTAMP;
+
+ALTER TABLE fenster.public.issues
+    ADD COLUMN issue_publish_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS articles_publish_at_idx ON fenster.public.articles (article_publish_at)
+    WHERE NOT article_published AND article_publish_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS issues_publish_at_idx ON fenster.public.issues (issue_publish_at)
+    WHERE NOT issue_published AND issue_publish_at IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,39 @@
+-- Add migration script here
+CREATE TYPE article_state AS ENUM ('draft', 'in_review', 'approved', 'published');
+
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_state    article_state NOT NULL DEFAULT 'draft',
+    ADD COLUMN article_reviewer TEXT REFERENCES fenster.public.users ON DELETE SET NULL;
+
+UPDATE fenster.public.articles
+SET article_state = 'published'
+WHERE article_published;
+
+DROP INDEX IF EXISTS fenster.public.articles_publish_at_idx;
+
+ALTER TABLE fenster.public.articles
+    DROP COLUMN article_published;
+
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_published BOOL GENERATED ALWAYS AS (article_state = 'published') STORED;
+
+CREATE INDEX IF NOT EXISTS articles_publish_at_idx ON fenster.public.articles (article_publish_at)
+    WHERE article_state = 'approved' AND article_publish_at IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS articles_reviewer_idx ON fenster.public.articles (article_reviewer)
+    WHERE article_reviewer IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS fenster.public.article_state_changes
+(
+    change_id      BIGSERIAL
+        CONSTRAINT article_state_changes_pk
+            PRIMARY KEY,
+    article_id     BIGINT        NOT NULL REFERENCES fenster.public.articles ON DELETE CASCADE,
+    change_from    article_state NOT NULL,
+    change_to      article_state NOT NULL,
+    change_user    TEXT          REFERENCES fenster.public.users ON DELETE SET NULL,
+    change_comment TEXT,
+    creation_date  TIMESTAMP     NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS article_state_changes_article_idx ON fenster.public.article_state_changes (article_id, creation_date);
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE fenster.public.users
+    ADD COLUMN user_totp_step BIGINT;
\ No newline at end of file
@@ -0,0 +1,23 @@
+-- Add migration script here
+ALTER TABLE fenster.public.articles ADD COLUMN article_text TEXT NOT NULL DEFAULT '';
+
+UPDATE fenster.public.articles a
+SET article_text = COALESCE(
+    replace(replace(replace(replace(replace(
+        regexp_replace((SELECT r.revision_content_html FROM fenster.public.article_revisions r
+            WHERE r.article_id = a.article_id ORDER BY r.revision_number DESC LIMIT 1), '<[^>]*>', ' ', 'g'),
+        '&lt;', '<'), '&gt;', '>'), '&quot;', '"'), '&#39;', ''''), '&amp;', '&'),
+    a.article_content);
+
+DROP INDEX IF EXISTS fenster.public.articles_search_idx;
+ALTER TABLE fenster.public.articles DROP COLUMN article_search;
+
+ALTER TABLE fenster.public.articles
+    ADD COLUMN article_search TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('german', article_title), 'A') ||
+        setweight(to_tsvector('english', article_title), 'A') ||
+        setweight(to_tsvector('german', article_text), 'B') ||
+        setweight(to_tsvector('english', article_text), 'B')
+        ) STORED;
+
+CREATE INDEX IF NOT EXISTS articles_search_idx ON fenster.public.articles USING GIN (article_search);
\ No newline at end of file
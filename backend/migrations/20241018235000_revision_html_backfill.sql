@@ -0,0 +1,10 @@
+-- Add migration script here
+UPDATE fenster.public.article_revisions r
+SET revision_content_html = COALESCE((
+    SELECT string_agg('<p>' || replace(replace(replace(replace(replace(replace(
+        btrim(p.paragraph, E' \t\r\n'),
+        '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;'), E'\n', '<br>') || '</p>', '' ORDER BY p.position)
+    FROM regexp_split_to_table(replace(r.revision_content, E'\r\n', E'\n'), E'\n\n') WITH ORDINALITY AS p(paragraph, position)
+    WHERE btrim(p.paragraph, E' \t\r\n') <> ''), '')
+WHERE r.revision_content_html IS NULL
+  AND r.revision_format = 'plain';
\ No newline at end of file
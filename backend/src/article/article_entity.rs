@@ -1,51 +1,285 @@
 use std::fmt::Debug;
 
 use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
 use tracing::error;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
+use crate::backend_config::BackendConfig;
 use crate::fenster_error::{error, FensterError, POSTGRES_ERROR};
-use crate::fenster_error::FensterError::Internal;
+use crate::fenster_error::FensterError::{Conflict, Internal};
+use crate::html;
+use crate::article::{article_revision, article_slug, article_state};
+use crate::article::article_format::ContentFormat;
+use crate::article::article_state::ArticleState;
+use crate::category::category_entity::Category;
+use crate::tag::tag_entity;
+use crate::tag::tag_entity::{Tag, TAGS_PER_ARTICLE};
+use crate::taxonomy;
 use crate::user;
-use crate::user::user_entity::User;
+use crate::user::user_entity::{Author, User};
+use crate::user::user_role::{Permission, Role};
+use crate::validation::{double_option, page_offset, Validate, Validator};
+
+const TITLE_MAX_LENGTH: usize = 200;
+const CONTENT_MAX_LENGTH: usize = 200_000;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Article {
+    pub(crate) id: i64,
     pub(crate) slug: String,
     pub(crate) title: String,
     pub(crate) content: String,
-    pub(crate) author: User,
-    pub(crate) published: bool,
+    pub(crate) format: ContentFormat,
+    pub(crate) content_html: String,
+    pub(crate) category: Option<Category>,
+    pub(crate) tags: Vec<Tag>,
+    pub(crate) author: Author,
+    pub(crate) state: ArticleState,
+    pub(crate) reviewer: Option<String>,
+    pub(crate) publish_at: Option<NaiveDateTime>,
+    pub(crate) creation_date: NaiveDateTime,
+    pub(crate) editing_date: NaiveDateTime,
 }
 
 #[derive(Deserialize)]
 pub struct CreateArticle {
     pub(crate) title: String,
-    pub(crate) slug: String,
+    pub(crate) slug: Option<String>,
     pub(crate) content: String,
-    pub(crate) author: String,
-    pub(crate) published: bool,
+    pub(crate) format: Option<ContentFormat>,
+    pub(crate) category: Option<String>,
+    pub(crate) tags: Option<Vec<String>>,
+    pub(crate) publish_at: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct EditArticle {
+    pub(crate) title: Option<String>,
+    pub(crate) slug: Option<String>,
+    pub(crate) content: Option<String>,
+    pub(crate) format: Option<ContentFormat>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub(crate) category: Option<Option<String>>,
+    pub(crate) tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub(crate) publish_at: Option<Option<NaiveDateTime>>,
+}
+
+impl Validate for CreateArticle {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("title", &self.title).length(1, TITLE_MAX_LENGTH);
+
+        if let Some(slug) = &self.slug {
+            validate_slug(slug, validator);
+        }
+
+        validator.field("content", &self.content).length(0, CONTENT_MAX_LENGTH);
+
+        validate_taxonomy(self.category.as_ref(), self.tags.as_ref(), validator);
+    }
+}
+
+impl Validate for EditArticle {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        if let Some(title) = &self.title {
+            validator.field("title", title).length(1, TITLE_MAX_LENGTH);
+        }
+
+        if let Some(slug) = &self.slug {
+            validate_slug(slug, validator);
+        }
+
+        if let Some(content) = &self.content {
+            validator.field("content", content).length(0, CONTENT_MAX_LENGTH);
+        }
+
+        validate_taxonomy(self.category.as_ref().and_then(Option::as_ref), self.tags.as_ref(), validator);
+    }
+}
+
+fn validate_slug(slug: &str, validator: &mut Validator) {
+    validator.field("slug", slug).slug()
+        .require(!article_slug::is_reserved(slug), "Is reserved and can not be used as slug.");
+}
+
+fn validate_taxonomy(category: Option<&String>, tags: Option<&Vec<String>>, validator: &mut Validator) {
+    if let Some(category) = category {
+        validator.field("category", category).slug();
+    }
+
+    if let Some(tags) = tags {
+        validator.field("tags", "")
+            .require(tags.len() <= TAGS_PER_ARTICLE, format!("Must contain at most {} tags.", TAGS_PER_ARTICLE).as_str());
+
+        for tag in tags {
+            taxonomy::validate_name("tags", tag, validator);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListArticles {
+    pub(crate) page: Option<i64>,
+    pub(crate) per_page: Option<i64>,
+    pub(crate) sort: Option<ArticleSort>,
+    pub(crate) order: Option<SortOrder>,
+    pub(crate) author: Option<String>,
+    pub(crate) published: Option<bool>,
+    pub(crate) state: Option<ArticleState>,
+    pub(crate) reviewer: Option<String>,
+    pub(crate) from: Option<NaiveDateTime>,
+    pub(crate) to: Option<NaiveDateTime>,
+    pub(crate) category: Option<String>,
+    pub(crate) tag: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArticleSort {
+    #[default]
+    CreationDate,
+    EditingDate,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ArticlePage {
+    pub(crate) articles: Vec<Article>,
+    pub(crate) page: i64,
+    pub(crate) per_page: i64,
+    pub(crate) total: i64,
 }
 
 impl Article {
-    pub async fn store(&self, pool: &PgPool) -> Result<(), FensterError> {
-        sqlx::query!(
+    pub async fn store(&mut self, pool: &PgPool) -> Result<(), FensterError> {
+        let mut transaction = pool.begin().await
+            .inspect_err(|err| {
+                error!("Error while starting transaction for article with article_slug ({}). - {}", self.slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 35)))?;
+
+        self.content_html = self.format.render(self.content.as_str());
+
+        let result = sqlx::query!(
             "INSERT INTO fenster.public.articles
-            (article_slug, article_title, article_content, article_author, article_published, editing_date)
-            VALUES($1, $2, $3, $4, $5,  NOW())",
-            &self.slug, &self.title, &self.content, &self.author.id, &self.published)
-            .execute(pool)
+            (article_slug, article_title, article_content, article_format, article_author, article_state, creation_date, editing_date,
+            article_category, article_publish_at, article_text)
+            VALUES($1, $2, $3, $4, $5, $6, $7, $8, (SELECT category_id FROM fenster.public.categories WHERE category_slug=$9), $10, $11)
+            RETURNING article_id",
+            &self.slug, &self.title, &self.content, self.format as ContentFormat, &self.author.id, self.state as ArticleState,
+            &self.creation_date, &self.editing_date, self.category.as_ref().map(|category| category.slug.clone()), self.publish_at,
+            html::text(self.content_html.as_str()))
+            .fetch_one(&mut *transaction)
             .await
+            .map_err(|err| slug_error(&self.slug, err, "saving", 1))?;
+
+        self.id = result.article_id;
+
+        article_revision::record(self, &self.author.id, &mut transaction).await?;
+        tag_entity::set_for_article(self.id, &self.tags, &mut transaction).await?;
+
+        transaction.commit().await
             .inspect_err(|err| {
-                error!("Error while saving article with article_slug ({}). - {}", self.slug, err)
+                error!("Error while committing article with article_slug ({}). - {}", self.slug, err)
             })
-            .map_err(|_| Internal(error(POSTGRES_ERROR, 1)))?;
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 83)))?;
+
+        Ok(())
+    }
+
+    pub async fn update(&mut self, editor: &User, pool: &PgPool) -> Result<(), FensterError> {
+        self.editing_date = Utc::now().naive_utc();
+
+        let mut transaction = pool.begin().await
+            .inspect_err(|err| {
+                error!("Error while starting transaction for article with article_id ({}). - {}", self.id, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 30)))?;
+
+        let previous = sqlx::query!(
+            r#"SELECT article_slug, article_title, article_content, article_format AS "article_format: ContentFormat"
+            FROM fenster.public.articles WHERE article_id=$1 FOR UPDATE"#, self.id)
+            .fetch_one(&mut *transaction)
+            .await
+            .inspect_err(|err| {
+                error!("Error while locking article with article_id ({}). - {}", self.id, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 31)))?;
+
+        if previous.article_slug != self.slug {
+            article_slug::retire(&previous.article_slug, &self.slug, self.id, &mut transaction).await?;
+        }
+
+        let content_changed = previous.article_content != self.content || previous.article_format != self.format;
+
+        let text = if content_changed {
+            self.content_html = self.format.render(self.content.as_str());
+            Some(html::text(self.content_html.as_str()))
+        } else {
+            None
+        };
+
+        sqlx::query!(
+            "UPDATE fenster.public.articles
+            SET article_slug=$2, article_title=$3, article_content=$4, article_format=$5, editing_date=$6,
+            article_category=(SELECT category_id FROM fenster.public.categories WHERE category_slug=$7), article_publish_at=$8,
+            article_text=COALESCE($9, article_text)
+            WHERE article_id=$1",
+            &self.id, &self.slug, &self.title, &self.content, self.format as ContentFormat, &self.editing_date,
+            self.category.as_ref().map(|category| category.slug.clone()), self.publish_at, text)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|err| slug_error(&self.slug, err, "updating", 11))?;
+
+        let changed = content_changed || previous.article_title != self.title;
+
+        if changed {
+            article_revision::record(self, &editor.id, &mut transaction).await?;
+        }
+
+        let reopen = changed && self.state == ArticleState::Approved;
+
+        if reopen {
+            let comment = Some("Changed after approval.".to_string());
+            article_state::record_transition(self, ArticleState::InReview, editor, comment, self.editing_date, &mut transaction).await?;
+        }
+
+        tag_entity::set_for_article(self.id, &self.tags, &mut transaction).await?;
+
+        transaction.commit().await
+            .inspect_err(|err| {
+                error!("Error while committing article with article_id ({}). - {}", self.id, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 84)))?;
+
+        if reopen {
+            self.state = ArticleState::InReview;
+        }
 
         Ok(())
     }
 }
 
+fn slug_error(slug: &str, err: sqlx::Error, action: &str, index: u16) -> FensterError {
+    match err {
+        sqlx::Error::Database(err) if err.is_unique_violation() =>
+            Conflict(format!("Article with given slug ({}) already exists.", slug)),
+        err => {
+            error!("Error while {} article with article_slug ({}). - {}", action, slug, err);
+            Internal(error(POSTGRES_ERROR, index))
+        }
+    }
+}
+
 pub async fn exists(slug: &str, pool: &PgPool) -> Result<bool, FensterError> {
     let result = sqlx::query!(
         "SELECT EXISTS (SELECT article_title) FROM fenster.public.articles WHERE article_slug=$1", slug)
@@ -62,8 +296,15 @@ pub async fn exists(slug: &str, pool: &PgPool) -> Result<bool, FensterError> {
 
 pub async fn fetch(slug: &str, pool: &PgPool) -> Result<Article, FensterError> {
     let article_result = sqlx::query!(
-        "SELECT article_slug, article_title, article_content, article_author, article_published
-         FROM fenster.public.articles WHERE article_slug=$1", slug)
+        r#"SELECT a.article_id, a.article_slug, a.article_title, a.article_content, a.article_format AS "article_format: ContentFormat",
+        r.revision_content_html AS "revision_content_html?", a.article_author,
+        a.article_state AS "article_state: ArticleState", a.article_reviewer, a.article_publish_at, a.creation_date, a.editing_date,
+        c.category_slug AS "category_slug?", c.category_name AS "category_name?"
+        FROM fenster.public.articles a
+        LEFT JOIN fenster.public.categories c ON c.category_id = a.article_category
+        LEFT JOIN LATERAL (SELECT revision_content_html FROM fenster.public.article_revisions
+            WHERE article_id = a.article_id ORDER BY revision_number DESC LIMIT 1) r ON TRUE
+        WHERE a.article_slug=$1"#, slug)
         .fetch_one(pool)
         .await
         .inspect_err(|err| {
@@ -72,12 +313,214 @@ pub async fn fetch(slug: &str, pool: &PgPool) -> Result<Article, FensterError> {
         .map_err(|_| Internal(error(POSTGRES_ERROR, 3)))?;
 
     let user_result = user::user_entity::fetch(article_result.article_author.as_str(), pool).await?;
+    let tags = tag_entity::for_articles(&[article_result.article_id], pool).await?
+        .remove(&article_result.article_id)
+        .unwrap_or_default();
 
     Ok(Article {
+        id: article_result.article_id,
         slug: article_result.article_slug,
         title: article_result.article_title,
+        content_html: article_result.revision_content_html
+            .unwrap_or_else(|| article_result.article_format.render(article_result.article_content.as_str())),
         content: article_result.article_content,
-        author: user_result,
-        published: article_result.article_published,
+        format: article_result.article_format,
+        category: category(article_result.category_slug, article_result.category_name),
+        tags,
+        author: Author::from(&user_result),
+        state: article_result.article_state,
+        reviewer: article_result.article_reviewer,
+        publish_at: article_result.article_publish_at,
+        creation_date: article_result.creation_date,
+        editing_date: article_result.editing_date,
+    })
+}
+
+pub async fn list(list: &ListArticles, viewer: Option<&User>, pool: &PgPool) -> Result<ArticlePage, FensterError> {
+    let page = list.page.unwrap_or(0).max(0);
+    let per_page = list.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = page_offset(page, per_page)?;
+    let sort_editing = list.sort.unwrap_or_default() == ArticleSort::EditingDate;
+    let ascending = list.order.unwrap_or_default() == SortOrder::Asc;
+
+    let viewer_id = viewer.map(|user| user.id.clone());
+    let manager = viewer.is_some_and(|user| user.has_permission(Permission::ManageArticles));
+
+    let total = sqlx::query!(
+        r#"SELECT COUNT(*) AS "total!"
+        FROM fenster.public.articles a
+        LEFT JOIN fenster.public.categories c ON c.category_id = a.article_category
+        WHERE ($1::TEXT IS NULL OR a.article_author = $1)
+        AND ($2::BOOL IS NULL OR a.article_published = $2)
+        AND ($3::TIMESTAMP IS NULL OR a.creation_date >= $3)
+        AND ($4::TIMESTAMP IS NULL OR a.creation_date <= $4)
+        AND (a.article_published OR a.article_author = $5 OR $6)
+        AND ($7::TEXT IS NULL OR c.category_slug = $7)
+        AND ($8::TEXT IS NULL OR EXISTS (SELECT 1 FROM fenster.public.article_tags at
+            JOIN fenster.public.tags t ON t.tag_id = at.tag_id WHERE at.article_id = a.article_id AND t.tag_slug = $8))
+        AND ($9::article_state IS NULL OR a.article_state = $9)
+        AND ($10::TEXT IS NULL OR a.article_reviewer = $10)"#,
+        list.author, list.published, list.from, list.to, viewer_id, manager, list.category, list.tag,
+        list.state as Option<ArticleState>, list.reviewer)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while counting articles. - {}", err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 76)))?
+        .total;
+
+    let rows = sqlx::query!(
+        r#"SELECT a.article_id, a.article_slug, a.article_title, a.article_content, a.article_format AS "article_format: ContentFormat",
+        r.revision_content_html AS "revision_content_html?", a.article_state AS "article_state: ArticleState",
+        a.article_reviewer, a.article_publish_at, a.creation_date, a.editing_date,
+        u.user_id, u.user_name, u.user_role AS "user_role: Role",
+        c.category_slug AS "category_slug?", c.category_name AS "category_name?"
+        FROM fenster.public.articles a
+        JOIN fenster.public.users u ON u.user_id = a.article_author
+        LEFT JOIN fenster.public.categories c ON c.category_id = a.article_category
+        LEFT JOIN LATERAL (SELECT revision_content_html FROM fenster.public.article_revisions
+            WHERE article_id = a.article_id ORDER BY revision_number DESC LIMIT 1) r ON TRUE
+        WHERE ($1::TEXT IS NULL OR a.article_author = $1)
+        AND ($2::BOOL IS NULL OR a.article_published = $2)
+        AND ($3::TIMESTAMP IS NULL OR a.creation_date >= $3)
+        AND ($4::TIMESTAMP IS NULL OR a.creation_date <= $4)
+        AND (a.article_published OR a.article_author = $5 OR $6)
+        AND ($7::TEXT IS NULL OR c.category_slug = $7)
+        AND ($8::TEXT IS NULL OR EXISTS (SELECT 1 FROM fenster.public.article_tags at
+            JOIN fenster.public.tags t ON t.tag_id = at.tag_id WHERE at.article_id = a.article_id AND t.tag_slug = $8))
+        AND ($9::article_state IS NULL OR a.article_state = $9)
+        AND ($10::TEXT IS NULL OR a.article_reviewer = $10)
+        ORDER BY
+        CASE WHEN $11 AND $12 THEN a.editing_date WHEN $12 THEN a.creation_date END ASC,
+        CASE WHEN $11 AND NOT $12 THEN a.editing_date WHEN NOT $12 THEN a.creation_date END DESC,
+        a.article_slug
+        LIMIT $13 OFFSET $14"#,
+        list.author, list.published, list.from, list.to, viewer_id, manager, list.category, list.tag,
+        list.state as Option<ArticleState>, list.reviewer, sort_editing, ascending, per_page, offset)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while listing articles. - {}", err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 13)))?;
+
+    let article_ids: Vec<i64> = rows.iter().map(|row| row.article_id).collect();
+    let mut tags = tag_entity::for_articles(&article_ids, pool).await?;
+
+    let articles = rows.into_iter().map(|row| Article {
+        id: row.article_id,
+        slug: row.article_slug,
+        title: row.article_title,
+        content_html: row.revision_content_html
+            .unwrap_or_else(|| row.article_format.render(row.article_content.as_str())),
+        content: row.article_content,
+        format: row.article_format,
+        category: category(row.category_slug, row.category_name),
+        tags: tags.remove(&row.article_id).unwrap_or_default(),
+        author: Author {
+            id: row.user_id,
+            name: row.user_name,
+            role: row.user_role,
+        },
+        state: row.article_state,
+        reviewer: row.article_reviewer,
+        publish_at: row.article_publish_at,
+        creation_date: row.creation_date,
+        editing_date: row.editing_date,
+    }).collect();
+
+    Ok(ArticlePage {
+        articles,
+        page,
+        per_page,
+        total,
     })
+}
+
+pub async fn list_for_issue(number: i32, include_unpublished: bool, pool: &PgPool) -> Result<Vec<Article>, FensterError> {
+    let rows = sqlx::query!(
+        r#"SELECT a.article_id, a.article_slug, a.article_title, a.article_content, a.article_format AS "article_format: ContentFormat",
+        r.revision_content_html AS "revision_content_html?", a.article_state AS "article_state: ArticleState",
+        a.article_reviewer, a.article_publish_at, a.creation_date, a.editing_date,
+        u.user_id, u.user_name, u.user_role AS "user_role: Role",
+        c.category_slug AS "category_slug?", c.category_name AS "category_name?"
+        FROM fenster.public.issue_articles ia
+        JOIN fenster.public.articles a ON a.article_id = ia.article_id
+        JOIN fenster.public.users u ON u.user_id = a.article_author
+        LEFT JOIN fenster.public.categories c ON c.category_id = a.article_category
+        LEFT JOIN LATERAL (SELECT revision_content_html FROM fenster.public.article_revisions
+            WHERE article_id = a.article_id ORDER BY revision_number DESC LIMIT 1) r ON TRUE
+        WHERE ia.issue_number=$1 AND (a.article_published OR $2)
+        ORDER BY ia.position"#,
+        number, include_unpublished)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching articles of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 59)))?;
+
+    let article_ids: Vec<i64> = rows.iter().map(|row| row.article_id).collect();
+    let mut tags = tag_entity::for_articles(&article_ids, pool).await?;
+
+    Ok(rows.into_iter().map(|row| Article {
+        id: row.article_id,
+        slug: row.article_slug,
+        title: row.article_title,
+        content_html: row.revision_content_html
+            .unwrap_or_else(|| row.article_format.render(row.article_content.as_str())),
+        content: row.article_content,
+        format: row.article_format,
+        category: category(row.category_slug, row.category_name),
+        tags: tags.remove(&row.article_id).unwrap_or_default(),
+        author: Author {
+            id: row.user_id,
+            name: row.user_name,
+            role: row.user_role,
+        },
+        state: row.article_state,
+        reviewer: row.article_reviewer,
+        publish_at: row.article_publish_at,
+        creation_date: row.creation_date,
+        editing_date: row.editing_date,
+    }).collect())
+}
+
+pub async fn publish_due(pool: &PgPool) -> Result<Vec<String>, FensterError> {
+    let rows = sqlx::query!(
+        "WITH published AS (UPDATE fenster.public.articles
+            SET article_state='published', article_publish_at=NULL, editing_date=NOW() AT TIME ZONE 'UTC'
+            WHERE article_state='approved' AND article_publish_at <= NOW() AT TIME ZONE 'UTC'
+            RETURNING article_id, article_slug),
+        changes AS (INSERT INTO fenster.public.article_state_changes (article_id, change_from, change_to, creation_date)
+            SELECT article_id, 'approved', 'published', NOW() AT TIME ZONE 'UTC' FROM published)
+        SELECT article_slug FROM published")
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while publishing due articles. - {}", err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 67)))?;
+
+    Ok(rows.into_iter().map(|row| row.article_slug).collect())
+}
+
+fn category(slug: Option<String>, name: Option<String>) -> Option<Category> {
+    Some(Category {
+        slug: slug?,
+        name: name?,
+    })
+}
+
+pub async fn delete(slug: &str, pool: &PgPool) -> Result<(), FensterError> {
+    sqlx::query!("DELETE FROM fenster.public.articles WHERE article_slug=$1", slug)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while deleting article with article_slug ({}). - {}", slug, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 12)))?;
+
+    Ok(())
 }
\ No newline at end of file
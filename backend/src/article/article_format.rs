@@ -0,0 +1,43 @@
+use ammonia::clean;
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+
+use crate::html::escape;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "content_format", rename_all = "lowercase")]
+pub enum ContentFormat {
+    Plain,
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ContentFormat {
+    pub fn render(&self, content: &str) -> String {
+        match self {
+            ContentFormat::Plain => render_plain(content),
+            ContentFormat::Markdown => clean(render_markdown(content).as_str()),
+            ContentFormat::Html => clean(content),
+        }
+    }
+}
+
+fn render_markdown(content: &str) -> String {
+    let parser = Parser::new_ext(content, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH);
+
+    let mut rendered = String::with_capacity(content.len() * 3 / 2);
+    html::push_html(&mut rendered, parser);
+
+    rendered
+}
+
+fn render_plain(content: &str) -> String {
+    content.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape(paragraph).replace('\n', "<br>")))
+        .collect()
+}
\ No newline at end of file
@@ -1,60 +1,387 @@
 use anyhow::Result;
+use chrono::Utc;
 use axum::{Json, Router};
-use axum::extract::{Path, State};
-use axum::routing::{get, post};
+use axum::extract::State;
+use axum::http::header::LOCATION;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use sqlx::PgPool;
 
 use crate::AppInject;
-use crate::article::{article_entity, article_entity::Article};
-use crate::article::article_entity::CreateArticle;
-use crate::fenster_error::FensterError;
-use crate::fenster_error::FensterError::{Conflict, NotFound, Unauthorized};
-use crate::user::user_entity;
+use crate::article::{article_entity, article_entity::Article, article_slug};
+use crate::article::article_entity::{ArticlePage, CreateArticle, EditArticle, ListArticles};
+use crate::article::{article_revision, article_search, article_state};
+use crate::article::article_search::{SearchArticles, SearchPage};
+use crate::article::article_revision::{DiffRevisions, Revision, RevisionDiff, RevisionSummary};
+use crate::article::article_state::{ArticleState, AssignReviewer, StateChange, TransitionArticle};
+use crate::auth::auth_extractor::{AuthenticatedUser, AuthorUser, MaybeAuthenticatedUser};
+use crate::category::category_entity::{Categories, Category};
+use crate::fenster_error::{FensterError, FieldError};
+use crate::fenster_error::FensterError::{Conflict, NotFound, Unauthorized, Validation};
+use crate::tag::tag_entity;
+use crate::taxonomy::Taxonomy;
+use crate::user;
+use crate::user::user_entity::{Author, User};
+use crate::user::user_role::Permission;
+use crate::validation::{PathParams, QueryParams, ValidJson};
 
 pub fn article_router() -> Router<AppInject> {
     Router::new()
-        .route("/", post(create_article))
-        .route("/:slug", get(get_article))
+        .route("/", get(list_articles).post(create_article))
+        .route("/search", get(search_articles))
+        .route("/:slug", get(get_article).patch(edit_article).delete(delete_article))
+        .route("/:slug/publish", put(publish_article))
+        .route("/:slug/unpublish", put(unpublish_article))
+        .route("/:slug/state", put(transition_article))
+        .route("/:slug/history", get(article_history))
+        .route("/:slug/reviewer", put(assign_reviewer))
+        .route("/:slug/revisions", get(list_revisions))
+        .route("/:slug/revisions/:number", get(get_revision))
+        .route("/:slug/revisions/:number/restore", post(restore_revision))
+        .route("/:slug/diff", get(diff_revisions))
 }
 
 pub async fn get_article(
     State(AppInject { postgres_pool, .. }): State<AppInject>,
-    Path(slug): Path<String>,
-) -> Result<Json<Article>, FensterError> {
+    MaybeAuthenticatedUser(user): MaybeAuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<Response, FensterError> {
     if !article_entity::exists(&slug, &postgres_pool).await? {
-        return Err(NotFound(format!("Article with given slug ({}) does not exist.", slug)));
+        return match article_slug::current_slug(&slug, &postgres_pool).await? {
+            Some(current) => Ok((
+                StatusCode::MOVED_PERMANENTLY,
+                [(LOCATION, format!("/article/{}", current))],
+            ).into_response()),
+            None => Err(NotFound(format!("Article with given slug ({}) does not exist.", slug))),
+        };
     }
 
     let article = article_entity::fetch(&slug, &postgres_pool).await?;
-    Ok(Json(article))
+
+    if article.state != ArticleState::Published && !user.is_some_and(|user| user.id == article.author.id || user.has_permission(Permission::ManageArticles)) {
+        return Err(NotFound(format!("Article with given slug ({}) does not exist.", slug)));
+    }
+
+    Ok(Json(article).into_response())
+}
+
+pub async fn list_articles(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    MaybeAuthenticatedUser(user): MaybeAuthenticatedUser,
+    QueryParams(list): QueryParams<ListArticles>,
+) -> Result<Json<ArticlePage>, FensterError> {
+    let page = article_entity::list(&list, user.as_ref(), &postgres_pool).await?;
+    Ok(Json(page))
+}
+
+pub async fn search_articles(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    QueryParams(search): QueryParams<SearchArticles>,
+) -> Result<Json<SearchPage>, FensterError> {
+    let page = article_search::search(&search, &postgres_pool).await?;
+    Ok(Json(page))
 }
 
 pub async fn create_article(
     State(AppInject { postgres_pool, .. }): State<AppInject>,
-    Json(create): Json<CreateArticle>,
+    AuthorUser(user): AuthorUser,
+    ValidJson(create): ValidJson<CreateArticle>,
 ) -> Result<Json<Article>, FensterError> {
-    if article_entity::exists(create.slug.as_str(), &postgres_pool).await? {
-        return Err(Conflict(format!("Article with given slug ({}) already exists.", create.slug)));
-    }
-
-    if !user_entity::exists_id(create.author.as_str(), &postgres_pool).await? {
-        return Err(NotFound(format!("Author with given id ({}) does not exist.", create.author)));
-    }
+    let slug = match create.slug {
+        Some(slug) if article_slug::is_taken(slug.as_str(), None, &postgres_pool).await? => {
+            return Err(Conflict(format!("Article with given slug ({}) already exists.", slug)));
+        }
+        Some(slug) => slug,
+        None => article_slug::unique_slug(article_slug::slugify(create.title.as_str()).as_str(), None, &postgres_pool).await?,
+    };
 
-    let user = user_entity::fetch(create.author.as_str(), &postgres_pool).await?;
+    let category = match create.category {
+        Some(category) => Some(require_category(category.as_str(), &postgres_pool).await?),
+        None => None,
+    };
 
-    if !user.author {
-        return Err(Unauthorized(format!("User with given id ({}) is not a author.", user.id)));
-    }
+    let now = Utc::now().naive_utc();
 
-    let article = Article {
-        slug: create.slug,
+    let mut article = Article {
+        id: 0,
+        slug,
         title: create.title,
         content: create.content,
-        author: user,
-        published: create.published,
+        format: create.format.unwrap_or_default(),
+        content_html: String::new(),
+        category,
+        tags: tag_entity::from_names(&create.tags.unwrap_or_default()),
+        author: Author::from(&user),
+        state: ArticleState::Draft,
+        reviewer: None,
+        publish_at: create.publish_at,
+        creation_date: now,
+        editing_date: now,
     };
 
     article.store(&postgres_pool).await?;
 
     Ok(Json(article))
+}
+
+pub async fn edit_article(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+    ValidJson(edit): ValidJson<EditArticle>,
+) -> Result<Json<Article>, FensterError> {
+    let mut article = require_article_editable(&user, &slug, &postgres_pool).await?;
+
+    match (edit.slug, &edit.title) {
+        (Some(slug), _) if slug != article.slug => {
+            if article_slug::is_taken(slug.as_str(), Some(article.id), &postgres_pool).await? {
+                return Err(Conflict(format!("Article with given slug ({}) already exists.", slug)));
+            }
+
+            article.slug = slug;
+        }
+        (None, Some(title)) if *title != article.title && article.state != ArticleState::Published => {
+            let base = article_slug::slugify(title.as_str());
+            article.slug = article_slug::unique_slug(base.as_str(), Some(article.id), &postgres_pool).await?;
+        }
+        _ => {}
+    }
+
+    if let Some(title) = edit.title {
+        article.title = title;
+    }
+
+    if let Some(content) = edit.content {
+        article.content = content;
+    }
+
+    if let Some(format) = edit.format {
+        article.format = format;
+    }
+
+    if let Some(category) = edit.category {
+        article.category = match category {
+            Some(category) => Some(require_category(category.as_str(), &postgres_pool).await?),
+            None => None,
+        };
+    }
+
+    if let Some(tags) = edit.tags {
+        article.tags = tag_entity::from_names(&tags);
+    }
+
+    if let Some(publish_at) = edit.publish_at {
+        article.publish_at = publish_at.filter(|_| article.state != ArticleState::Published);
+    }
+
+    article.update(&user, &postgres_pool).await?;
+    Ok(Json(article))
+}
+
+pub async fn delete_article(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<StatusCode, FensterError> {
+    let article = require_article_access(&user, &slug, &postgres_pool).await?;
+
+    if article.state == ArticleState::Published {
+        user.require_permission(Permission::ManageArticles)?;
+    }
+
+    article_entity::delete(&slug, &postgres_pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn publish_article(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<Json<Article>, FensterError> {
+    change_state(&user, &slug, ArticleState::Published, None, &postgres_pool).await
+}
+
+pub async fn unpublish_article(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<Json<Article>, FensterError> {
+    change_state(&user, &slug, ArticleState::Draft, None, &postgres_pool).await
+}
+
+pub async fn transition_article(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+    ValidJson(transition): ValidJson<TransitionArticle>,
+) -> Result<Json<Article>, FensterError> {
+    change_state(&user, &slug, transition.state, transition.comment, &postgres_pool).await
+}
+
+pub async fn article_history(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<Json<Vec<StateChange>>, FensterError> {
+    let article = require_article_access(&user, &slug, &postgres_pool).await?;
+
+    let history = article_state::history(article.id, &postgres_pool).await?;
+    Ok(Json(history))
+}
+
+pub async fn assign_reviewer(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+    ValidJson(assign): ValidJson<AssignReviewer>,
+) -> Result<Json<Article>, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    let mut article = require_article_access(&user, &slug, &postgres_pool).await?;
+
+    if let Some(reviewer) = &assign.reviewer {
+        require_reviewer(reviewer, &article, &postgres_pool).await?;
+    }
+
+    article_state::assign_reviewer(article.id, assign.reviewer.as_deref(), &postgres_pool).await?;
+    article.reviewer = assign.reviewer;
+
+    Ok(Json(article))
+}
+
+pub async fn list_revisions(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<Json<Vec<RevisionSummary>>, FensterError> {
+    let article = require_article_access(&user, &slug, &postgres_pool).await?;
+
+    let revisions = article_revision::list(article.id, &postgres_pool).await?;
+    Ok(Json(revisions))
+}
+
+pub async fn get_revision(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams((slug, number)): PathParams<(String, i32)>,
+) -> Result<Json<Revision>, FensterError> {
+    let article = require_article_access(&user, &slug, &postgres_pool).await?;
+
+    let revision = article_revision::fetch(article.id, number, &postgres_pool).await?;
+    Ok(Json(revision))
+}
+
+pub async fn diff_revisions(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+    QueryParams(diff): QueryParams<DiffRevisions>,
+) -> Result<Json<RevisionDiff>, FensterError> {
+    let article = require_article_access(&user, &slug, &postgres_pool).await?;
+
+    let from = article_revision::fetch(article.id, diff.from, &postgres_pool).await?;
+    let to = article_revision::fetch(article.id, diff.to, &postgres_pool).await?;
+
+    Ok(Json(article_revision::diff(&from, &to)))
+}
+
+pub async fn restore_revision(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams((slug, number)): PathParams<(String, i32)>,
+) -> Result<Json<Article>, FensterError> {
+    let mut article = require_article_editable(&user, &slug, &postgres_pool).await?;
+
+    let revision = article_revision::fetch(article.id, number, &postgres_pool).await?;
+
+    article.title = revision.title;
+    article.content = revision.content;
+    article.format = revision.format;
+    article.update(&user, &postgres_pool).await?;
+
+    Ok(Json(article))
+}
+
+async fn change_state(
+    user: &User,
+    slug: &str,
+    state: ArticleState,
+    comment: Option<String>,
+    pool: &PgPool,
+) -> Result<Json<Article>, FensterError> {
+    let mut article = require_article_access(user, slug, pool).await?;
+
+    article_state::require_transition(&article, state, user, comment.as_deref())?;
+    article_state::transition(&mut article, state, user, comment, pool).await?;
+
+    Ok(Json(article))
+}
+
+async fn require_reviewer(id: &str, article: &Article, pool: &PgPool) -> Result<(), FensterError> {
+    let reviewer = user::user_entity::fetch(id, pool).await
+        .map_err(|err| match err {
+            NotFound(_) => Validation(vec![FieldError {
+                field: "reviewer".to_string(),
+                message: format!("User with given id ({}) does not exist.", id),
+            }]),
+            err => err,
+        })?;
+
+    let message = if !reviewer.role.has(Permission::ManageArticles) {
+        format!("User with given id ({}) may not review articles.", id)
+    } else if reviewer.id == article.author.id {
+        format!("User with given id ({}) may not review their own article.", id)
+    } else {
+        return Ok(());
+    };
+
+    Err(Validation(vec![FieldError {
+        field: "reviewer".to_string(),
+        message,
+    }]))
+}
+
+async fn require_category(slug: &str, pool: &PgPool) -> Result<Category, FensterError> {
+    Categories::fetch(slug, pool).await
+        .map_err(|err| match err {
+            NotFound(message) => Validation(vec![FieldError {
+                field: "category".to_string(),
+                message,
+            }]),
+            err => err,
+        })
+}
+
+async fn require_article_access(
+    user: &User,
+    slug: &str,
+    pool: &PgPool,
+) -> Result<Article, FensterError> {
+    if !article_entity::exists(slug, pool).await? {
+        return Err(NotFound(format!("Article with given slug ({}) does not exist.", slug)));
+    }
+
+    let article = article_entity::fetch(slug, pool).await?;
+
+    if article.author.id != user.id && !user.has_permission(Permission::ManageArticles) {
+        return Err(Unauthorized(format!("User with given id ({}) may not modify article ({}).", user.id, slug)));
+    }
+
+    Ok(article)
+}
+
+async fn require_article_editable(
+    user: &User,
+    slug: &str,
+    pool: &PgPool,
+) -> Result<Article, FensterError> {
+    let article = require_article_access(user, slug, pool).await?;
+
+    if article.state != ArticleState::Draft && !user.has_permission(Permission::ManageArticles) {
+        return Err(Conflict(format!(
+            "Article with given slug ({}) is {} and may only be edited as draft.", slug, article.state.name())));
+    }
+
+    Ok(article)
 }
\ No newline at end of file
@@ -0,0 +1,204 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::article::article_entity::Article;
+use crate::article::article_format::ContentFormat;
+use crate::fenster_error::{error, FensterError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Internal, NotFound};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Revision {
+    pub(crate) number: i32,
+    pub(crate) author: Option<String>,
+    pub(crate) title: String,
+    pub(crate) content: String,
+    pub(crate) format: ContentFormat,
+    pub(crate) creation_date: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RevisionSummary {
+    pub(crate) number: i32,
+    pub(crate) author: Option<String>,
+    pub(crate) title: String,
+    pub(crate) creation_date: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+pub struct DiffRevisions {
+    pub(crate) from: i32,
+    pub(crate) to: i32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RevisionDiff {
+    pub(crate) from: i32,
+    pub(crate) to: i32,
+    pub(crate) title: Vec<DiffLine>,
+    pub(crate) content: Vec<DiffLine>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiffLine {
+    pub(crate) change: DiffChange,
+    pub(crate) old_line: Option<usize>,
+    pub(crate) new_line: Option<usize>,
+    pub(crate) text: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffChange {
+    Equal,
+    Insert,
+    Delete,
+}
+
+pub async fn record(
+    article: &Article,
+    editor: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), FensterError> {
+    sqlx::query!(
+        "INSERT INTO fenster.public.article_revisions
+        (article_id, revision_number, revision_author, revision_title, revision_content, revision_format,
+        revision_content_html, creation_date)
+        SELECT $1, COALESCE(MAX(revision_number), 0) + 1, $2, $3, $4, $5, $6, $7
+        FROM fenster.public.article_revisions WHERE article_id=$1",
+        article.id, editor, &article.title, &article.content, article.format as ContentFormat,
+        &article.content_html, &article.editing_date)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while recording revision of article with article_id ({}). - {}", article.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 32)))?;
+
+    Ok(())
+}
+
+pub async fn list(article_id: i64, pool: &PgPool) -> Result<Vec<RevisionSummary>, FensterError> {
+    let rows = sqlx::query!(
+        "SELECT revision_number, revision_author, revision_title, creation_date
+        FROM fenster.public.article_revisions WHERE article_id=$1 ORDER BY revision_number DESC",
+        article_id)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while listing revisions of article with article_id ({}). - {}", article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 33)))?;
+
+    Ok(rows.into_iter().map(|row| RevisionSummary {
+        number: row.revision_number,
+        author: row.revision_author,
+        title: row.revision_title,
+        creation_date: row.creation_date,
+    }).collect())
+}
+
+pub async fn fetch(article_id: i64, number: i32, pool: &PgPool) -> Result<Revision, FensterError> {
+    let row = sqlx::query!(
+        r#"SELECT revision_number, revision_author, revision_title, revision_content,
+        revision_format AS "revision_format: ContentFormat", creation_date
+        FROM fenster.public.article_revisions WHERE article_id=$1 AND revision_number=$2"#,
+        article_id, number)
+        .fetch_optional(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching revision ({}) of article with article_id ({}). - {}", number, article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 34)))?
+        .ok_or(NotFound(format!("Revision with given number ({}) does not exist.", number)))?;
+
+    Ok(Revision {
+        number: row.revision_number,
+        author: row.revision_author,
+        title: row.revision_title,
+        content: row.revision_content,
+        format: row.revision_format,
+        creation_date: row.creation_date,
+    })
+}
+
+pub fn diff(from: &Revision, to: &Revision) -> RevisionDiff {
+    RevisionDiff {
+        from: from.number,
+        to: to.number,
+        title: diff_lines(from.title.as_str(), to.title.as_str()),
+        content: diff_lines(from.content.as_str(), to.content.as_str()),
+    }
+}
+
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new).iter_all_changes().map(|change| DiffLine {
+        change: match change.tag() {
+            ChangeTag::Equal => DiffChange::Equal,
+            ChangeTag::Insert => DiffChange::Insert,
+            ChangeTag::Delete => DiffChange::Delete,
+        },
+        old_line: change.old_index().map(|index| index + 1),
+        new_line: change.new_index().map(|index| index + 1),
+        text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(diff: &[DiffLine]) -> Vec<(DiffChange, Option<usize>, Option<usize>, &str)> {
+        diff.iter().map(|line| (line.change, line.old_line, line.new_line, line.text.as_str())).collect()
+    }
+
+    fn revision(number: i32, title: &str, content: &str) -> Revision {
+        Revision {
+            number,
+            author: None,
+            title: title.to_string(),
+            content: content.to_string(),
+            format: ContentFormat::Plain,
+            creation_date: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn diff_lines_of_equal_text() {
+        assert_eq!(lines(&diff_lines("a\nb", "a\nb")), vec![
+            (DiffChange::Equal, Some(1), Some(1), "a"),
+            (DiffChange::Equal, Some(2), Some(2), "b"),
+        ]);
+    }
+
+    #[test]
+    fn diff_lines_numbers_changed_lines() {
+        assert_eq!(lines(&diff_lines("a\nb\nc\n", "a\nx\nc\nd\n")), vec![
+            (DiffChange::Equal, Some(1), Some(1), "a"),
+            (DiffChange::Delete, Some(2), None, "b"),
+            (DiffChange::Insert, None, Some(2), "x"),
+            (DiffChange::Equal, Some(3), Some(3), "c"),
+            (DiffChange::Insert, None, Some(4), "d"),
+        ]);
+    }
+
+    #[test]
+    fn diff_lines_strips_line_endings() {
+        assert_eq!(lines(&diff_lines("a\r\n", "")), vec![(DiffChange::Delete, Some(1), None, "a")]);
+    }
+
+    #[test]
+    fn diff_compares_title_and_content() {
+        let diff = diff(&revision(1, "Old", "same"), &revision(3, "New", "same"));
+
+        assert_eq!((diff.from, diff.to), (1, 3));
+        assert_eq!(lines(&diff.title), vec![
+            (DiffChange::Delete, Some(1), None, "Old"),
+            (DiffChange::Insert, None, Some(1), "New"),
+        ]);
+        assert_eq!(lines(&diff.content), vec![(DiffChange::Equal, Some(1), Some(1), "same")]);
+    }
+}
\ No newline at end of file
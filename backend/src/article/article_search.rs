@@ -0,0 +1,132 @@
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::fenster_error::{error, FensterError, FieldError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Internal, Validation};
+use crate::html;
+use crate::validation::page_offset;
+
+const QUERY_MAX_LENGTH: usize = 200;
+const HIGHLIGHT_START: &str = "\u{2}";
+const HIGHLIGHT_STOP: &str = "\u{3}";
+
+#[derive(Deserialize)]
+pub struct SearchArticles {
+    pub(crate) q: String,
+    pub(crate) page: Option<i64>,
+    pub(crate) per_page: Option<i64>,
+    pub(crate) category: Option<String>,
+    pub(crate) tag: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchHit {
+    pub(crate) slug: String,
+    pub(crate) title: String,
+    pub(crate) snippet: String,
+    pub(crate) rank: f32,
+    pub(crate) author: String,
+    pub(crate) creation_date: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchPage {
+    pub(crate) hits: Vec<SearchHit>,
+    pub(crate) page: i64,
+    pub(crate) per_page: i64,
+    pub(crate) total: i64,
+}
+
+pub async fn search(search: &SearchArticles, pool: &PgPool) -> Result<SearchPage, FensterError> {
+    let query = search.q.trim();
+
+    if query.is_empty() || query.chars().count() > QUERY_MAX_LENGTH {
+        return Err(Validation(vec![FieldError {
+            field: "q".to_string(),
+            message: format!("Must be between 1 and {} characters long.", QUERY_MAX_LENGTH),
+        }]));
+    }
+
+    let page = search.page.unwrap_or(0).max(0);
+    let per_page = search.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = page_offset(page, per_page)?;
+
+    let total = sqlx::query!(
+        r#"WITH query AS (
+            SELECT websearch_to_tsquery('german', $1) || websearch_to_tsquery('english', $1) AS q
+        )
+        SELECT COUNT(*) AS "total!"
+        FROM fenster.public.articles a
+        CROSS JOIN query
+        LEFT JOIN fenster.public.categories c ON c.category_id = a.article_category
+        WHERE a.article_published AND a.article_search @@ query.q
+        AND ($2::TEXT IS NULL OR c.category_slug = $2)
+        AND ($3::TEXT IS NULL OR EXISTS (SELECT 1 FROM fenster.public.article_tags at
+            JOIN fenster.public.tags t ON t.tag_id = at.tag_id WHERE at.article_id = a.article_id AND t.tag_slug = $3))"#,
+        query, search.category, search.tag)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while counting search results for ({}). - {}", query, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 77)))?
+        .total;
+
+    let rows = sqlx::query!(
+        r#"WITH query AS (
+            SELECT websearch_to_tsquery('german', $1) AS de, websearch_to_tsquery('english', $1) AS en,
+            websearch_to_tsquery('german', $1) || websearch_to_tsquery('english', $1) AS q,
+            'StartSel=' || $2 || ', StopSel=' || $3 || ', MaxFragments=2, MaxWords=30, MinWords=10' AS options
+        ), hits AS (
+            SELECT a.article_slug, a.article_title, a.article_author, a.creation_date, a.article_text,
+            ts_rank_cd(a.article_search, query.q) AS rank
+            FROM fenster.public.articles a
+            CROSS JOIN query
+            LEFT JOIN fenster.public.categories c ON c.category_id = a.article_category
+            WHERE a.article_published AND a.article_search @@ query.q
+            AND ($6::TEXT IS NULL OR c.category_slug = $6)
+            AND ($7::TEXT IS NULL OR EXISTS (SELECT 1 FROM fenster.public.article_tags at
+                JOIN fenster.public.tags t ON t.tag_id = at.tag_id WHERE at.article_id = a.article_id AND t.tag_slug = $7))
+            ORDER BY rank DESC, a.creation_date DESC
+            LIMIT $4 OFFSET $5
+        )
+        SELECT h.article_slug, h.article_title, h.article_author, h.creation_date, h.rank AS "rank!",
+        CASE WHEN strpos(german.snippet, $2) > 0 THEN german.snippet
+            ELSE ts_headline('english', h.article_text, query.en, query.options) END AS "snippet!"
+        FROM hits h
+        CROSS JOIN query
+        CROSS JOIN LATERAL (SELECT ts_headline('german', h.article_text, query.de, query.options) AS snippet) german
+        ORDER BY h.rank DESC, h.creation_date DESC"#,
+        query, HIGHLIGHT_START, HIGHLIGHT_STOP, per_page, offset, search.category, search.tag)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while searching articles for ({}). - {}", query, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 36)))?;
+
+    let hits = rows.into_iter().map(|row| SearchHit {
+        slug: row.article_slug,
+        title: row.article_title,
+        snippet: highlight(row.snippet.as_str()),
+        rank: row.rank,
+        author: row.article_author,
+        creation_date: row.creation_date,
+    }).collect();
+
+    Ok(SearchPage {
+        hits,
+        page,
+        per_page,
+        total,
+    })
+}
+
+fn highlight(snippet: &str) -> String {
+    html::escape(snippet)
+        .replace(HIGHLIGHT_START, "<mark>")
+        .replace(HIGHLIGHT_STOP, "</mark>")
+}
\ No newline at end of file
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::fenster_error::{error, FensterError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::Internal;
+
+const SLUG_MAX_LENGTH: usize = 120;
+const FALLBACK_SLUG: &str = "article";
+const RESERVED_SLUGS: &[&str] = &["search"];
+
+pub fn slugify(title: &str) -> String {
+    let slug = transliterate(title);
+
+    if slug.is_empty() {
+        return FALLBACK_SLUG.to_string();
+    }
+
+    slug
+}
+
+pub fn transliterate(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+
+    for c in title.chars().flat_map(char::to_lowercase) {
+        match c {
+            'ä' => slug.push_str("ae"),
+            'ö' => slug.push_str("oe"),
+            'ü' => slug.push_str("ue"),
+            'ß' => slug.push_str("ss"),
+            c if c.is_ascii_alphanumeric() => slug.push(c),
+            _ => if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-')
+            },
+        }
+    }
+
+    slug.truncate(SLUG_MAX_LENGTH);
+    slug.trim_end_matches('-').to_string()
+}
+
+pub fn is_reserved(slug: &str) -> bool {
+    RESERVED_SLUGS.contains(&slug)
+}
+
+pub async fn unique_slug(base: &str, article_id: Option<i64>, pool: &PgPool) -> Result<String, FensterError> {
+    let mut candidate = base.to_string();
+    let mut suffix = 2;
+
+    while is_taken(candidate.as_str(), article_id, pool).await? {
+        candidate = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+
+    Ok(candidate)
+}
+
+pub async fn is_taken(slug: &str, article_id: Option<i64>, pool: &PgPool) -> Result<bool, FensterError> {
+    if is_reserved(slug) {
+        return Ok(true);
+    }
+
+    let result = sqlx::query!(
+        r#"SELECT (
+            EXISTS (SELECT 1 FROM fenster.public.articles WHERE article_slug=$1 AND article_id IS DISTINCT FROM $2)
+            OR EXISTS (SELECT 1 FROM fenster.public.article_slugs WHERE slug=$1 AND article_id IS DISTINCT FROM $2)
+        ) AS "taken!""#,
+        slug, article_id)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while checking whether slug ({}) is taken. - {}", slug, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 26)))?;
+
+    Ok(result.taken)
+}
+
+pub async fn retire(
+    old_slug: &str,
+    new_slug: &str,
+    article_id: i64,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), FensterError> {
+    sqlx::query!(
+        "INSERT INTO fenster.public.article_slugs (slug, article_id) VALUES ($1, $2)
+        ON CONFLICT (slug) DO UPDATE SET article_id=$2, retired_at=NOW()",
+        old_slug, article_id)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while retiring slug ({}) of article ({}). - {}", old_slug, article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 27)))?;
+
+    sqlx::query!(
+        "DELETE FROM fenster.public.article_slugs WHERE slug=$1 AND article_id=$2",
+        new_slug, article_id)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while reclaiming slug ({}) of article ({}). - {}", new_slug, article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 28)))?;
+
+    Ok(())
+}
+
+pub async fn current_slug(old_slug: &str, pool: &PgPool) -> Result<Option<String>, FensterError> {
+    let result = sqlx::query!(
+        "SELECT a.article_slug FROM fenster.public.article_slugs s
+        JOIN fenster.public.articles a ON a.article_id = s.article_id
+        WHERE s.slug=$1",
+        old_slug)
+        .fetch_optional(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while resolving old slug ({}). - {}", old_slug, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 29)))?;
+
+    Ok(result.map(|row| row.article_slug))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliterate_lowercases_and_joins_words() {
+        assert_eq!(transliterate("Hello World"), "hello-world");
+        assert_eq!(transliterate("  Rust 2024: What's new?  "), "rust-2024-what-s-new");
+    }
+
+    #[test]
+    fn transliterate_replaces_umlauts() {
+        assert_eq!(transliterate("Größe über Äpfel"), "groesse-ueber-aepfel");
+    }
+
+    #[test]
+    fn transliterate_drops_other_characters() {
+        assert_eq!(transliterate("--Café №1--"), "caf-1");
+        assert_eq!(transliterate("日本語"), "");
+    }
+
+    #[test]
+    fn transliterate_truncates_without_trailing_dash() {
+        let title = format!("{} word", "a".repeat(SLUG_MAX_LENGTH - 1));
+
+        assert_eq!(transliterate(&title), "a".repeat(SLUG_MAX_LENGTH - 1));
+    }
+
+    #[test]
+    fn slugify_falls_back_for_empty_slugs() {
+        assert_eq!(slugify("Hello World"), "hello-world");
+        assert_eq!(slugify("?!"), FALLBACK_SLUG);
+    }
+
+    #[test]
+    fn reserved_slugs() {
+        assert!(is_reserved("search"));
+        assert!(!is_reserved("searching"));
+    }
+}
\ No newline at end of file
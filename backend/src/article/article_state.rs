@@ -0,0 +1,366 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::article::article_entity::Article;
+use crate::backend_config::BackendConfig;
+use crate::fenster_error::{error, FensterError, FieldError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Conflict, Internal, Unauthorized, Validation};
+use crate::user::user_entity::User;
+use crate::user::user_role::Permission;
+use crate::validation::{Validate, Validator};
+
+const COMMENT_MAX_LENGTH: usize = 2000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "article_state", rename_all = "snake_case")]
+pub enum ArticleState {
+    #[default]
+    Draft,
+    InReview,
+    Approved,
+    Published,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StateChange {
+    pub(crate) from: ArticleState,
+    pub(crate) to: ArticleState,
+    pub(crate) user: Option<String>,
+    pub(crate) comment: Option<String>,
+    pub(crate) creation_date: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+pub struct TransitionArticle {
+    pub(crate) state: ArticleState,
+    pub(crate) comment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignReviewer {
+    pub(crate) reviewer: Option<String>,
+}
+
+impl Validate for TransitionArticle {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        if let Some(comment) = &self.comment {
+            validator.field("comment", comment).length(1, COMMENT_MAX_LENGTH);
+        }
+    }
+}
+
+impl Validate for AssignReviewer {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        if let Some(reviewer) = &self.reviewer {
+            validator.field("reviewer", reviewer).id();
+        }
+    }
+}
+
+impl ArticleState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArticleState::Draft => "draft",
+            ArticleState::InReview => "in_review",
+            ArticleState::Approved => "approved",
+            ArticleState::Published => "published",
+        }
+    }
+}
+
+pub fn require_transition(
+    article: &Article,
+    to: ArticleState,
+    user: &User,
+    comment: Option<&str>,
+) -> Result<(), FensterError> {
+    let from = article.state;
+    let is_author = article.author.id == user.id;
+    let is_reviewer = article.reviewer.as_ref().is_none_or(|reviewer| *reviewer == user.id);
+
+    if from == to {
+        return Err(Conflict(format!("Article with given slug ({}) is already {}.", article.slug, to.name())));
+    }
+
+    match (from, to) {
+        (ArticleState::Draft, ArticleState::InReview)
+        | (ArticleState::Approved, ArticleState::Draft)
+        | (ArticleState::Published, ArticleState::Draft) if is_author => Ok(()),
+        (ArticleState::Draft, ArticleState::InReview)
+        | (ArticleState::Approved, ArticleState::Draft)
+        | (ArticleState::Published, ArticleState::Draft)
+        | (ArticleState::Approved, ArticleState::Published) => user.require_permission(Permission::ManageArticles),
+        (ArticleState::InReview, ArticleState::Draft) if is_author => Ok(()),
+        (ArticleState::InReview, ArticleState::Draft) | (ArticleState::InReview, ArticleState::Approved) => {
+            user.require_permission(Permission::ManageArticles)?;
+
+            if !is_reviewer {
+                return Err(Unauthorized(format!(
+                    "User with given id ({}) is not the reviewer of article ({}).", user.id, article.slug)));
+            }
+
+            if to == ArticleState::Approved && is_author {
+                return Err(Unauthorized(format!(
+                    "User with given id ({}) may not approve their own article ({}).", user.id, article.slug)));
+            }
+
+            if to == ArticleState::Draft && comment.is_none() {
+                return Err(Validation(vec![FieldError {
+                    field: "comment".to_string(),
+                    message: "Must explain which changes are requested.".to_string(),
+                }]));
+            }
+
+            Ok(())
+        }
+        _ => Err(Conflict(format!(
+            "Article with given slug ({}) can not move from {} to {}.", article.slug, from.name(), to.name()))),
+    }
+}
+
+pub async fn transition(
+    article: &mut Article,
+    to: ArticleState,
+    user: &User,
+    comment: Option<String>,
+    pool: &PgPool,
+) -> Result<(), FensterError> {
+    let now = Utc::now().naive_utc();
+
+    let mut transaction = pool.begin().await
+        .inspect_err(|err| {
+            error!("Error while starting transaction for state of article with article_id ({}). - {}", article.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 69)))?;
+
+    record_transition(article, to, user, comment, now, &mut transaction).await?;
+
+    transaction.commit().await
+        .inspect_err(|err| {
+            error!("Error while committing state of article with article_id ({}). - {}", article.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 82)))?;
+
+    if to == ArticleState::Published {
+        article.publish_at = None;
+    }
+
+    article.state = to;
+    article.editing_date = now;
+
+    Ok(())
+}
+
+pub async fn record_transition(
+    article: &Article,
+    to: ArticleState,
+    user: &User,
+    comment: Option<String>,
+    now: NaiveDateTime,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), FensterError> {
+    let result = sqlx::query!(
+        "UPDATE fenster.public.articles
+        SET article_state=$3, article_publish_at=CASE WHEN $3='published'::article_state THEN NULL ELSE article_publish_at END, editing_date=$4
+        WHERE article_id=$1 AND article_state=$2",
+        article.id, article.state as ArticleState, to as ArticleState, now)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while changing state of article with article_id ({}). - {}", article.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 70)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(Conflict(format!("Article with given slug ({}) is no longer {}.", article.slug, article.state.name())));
+    }
+
+    sqlx::query!(
+        "INSERT INTO fenster.public.article_state_changes
+        (article_id, change_from, change_to, change_user, change_comment, creation_date)
+        VALUES ($1, $2, $3, $4, $5, $6)",
+        article.id, article.state as ArticleState, to as ArticleState, &user.id, comment, now)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while recording state change of article with article_id ({}). - {}", article.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 71)))?;
+
+    Ok(())
+}
+
+pub async fn history(article_id: i64, pool: &PgPool) -> Result<Vec<StateChange>, FensterError> {
+    let rows = sqlx::query!(
+        r#"SELECT change_from AS "change_from: ArticleState", change_to AS "change_to: ArticleState",
+        change_user, change_comment, creation_date
+        FROM fenster.public.article_state_changes WHERE article_id=$1 ORDER BY creation_date DESC, change_id DESC"#,
+        article_id)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while listing state changes of article with article_id ({}). - {}", article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 72)))?;
+
+    Ok(rows.into_iter().map(|row| StateChange {
+        from: row.change_from,
+        to: row.change_to,
+        user: row.change_user,
+        comment: row.change_comment,
+        creation_date: row.creation_date,
+    }).collect())
+}
+
+pub async fn assign_reviewer(article_id: i64, reviewer: Option<&str>, pool: &PgPool) -> Result<(), FensterError> {
+    sqlx::query!(
+        "UPDATE fenster.public.articles SET article_reviewer=$2 WHERE article_id=$1", article_id, reviewer)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while assigning reviewer to article with article_id ({}). - {}", article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 73)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::article::article_format::ContentFormat;
+    use crate::fenster_error::FensterError::Forbidden;
+    use crate::user::user_entity::Author;
+    use crate::user::user_role::Role;
+
+    use super::*;
+
+    fn user(id: &str, role: Role) -> User {
+        User {
+            id: id.to_string(),
+            name: id.to_string(),
+            email: format!("{}@example.com", id),
+            role,
+            verified: true,
+            totp_enabled: false,
+            totp_required: false,
+        }
+    }
+
+    fn article(state: ArticleState, author: &User, reviewer: Option<&str>) -> Article {
+        Article {
+            id: 1,
+            slug: "article".to_string(),
+            title: "Article".to_string(),
+            content: String::new(),
+            format: ContentFormat::Plain,
+            content_html: String::new(),
+            category: None,
+            tags: vec![],
+            author: Author::from(author),
+            state,
+            reviewer: reviewer.map(str::to_string),
+            publish_at: None,
+            creation_date: NaiveDateTime::default(),
+            editing_date: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn same_state_is_a_conflict() {
+        let author = user("author", Role::Author);
+        let article = article(ArticleState::Draft, &author, None);
+
+        assert!(matches!(require_transition(&article, ArticleState::Draft, &author, None), Err(Conflict(_))));
+    }
+
+    #[test]
+    fn author_submits_and_withdraws_own_article() {
+        let author = user("author", Role::Author);
+
+        assert!(require_transition(&article(ArticleState::Draft, &author, None), ArticleState::InReview, &author, None).is_ok());
+        assert!(require_transition(&article(ArticleState::InReview, &author, None), ArticleState::Draft, &author, None).is_ok());
+        assert!(require_transition(&article(ArticleState::Published, &author, None), ArticleState::Draft, &author, None).is_ok());
+    }
+
+    #[test]
+    fn other_authors_can_not_submit() {
+        let author = user("author", Role::Author);
+        let other = user("other", Role::Author);
+        let article = article(ArticleState::Draft, &author, None);
+
+        assert!(matches!(require_transition(&article, ArticleState::InReview, &other, None), Err(Unauthorized(_))));
+    }
+
+    #[test]
+    fn reviewer_approves() {
+        let author = user("author", Role::Author);
+        let editor = user("editor", Role::Editor);
+
+        assert!(require_transition(&article(ArticleState::InReview, &author, None), ArticleState::Approved, &editor, None).is_ok());
+        assert!(require_transition(&article(ArticleState::InReview, &author, Some("editor")), ArticleState::Approved, &editor, None).is_ok());
+    }
+
+    #[test]
+    fn only_assigned_reviewer_approves() {
+        let author = user("author", Role::Author);
+        let editor = user("editor", Role::Editor);
+        let article = article(ArticleState::InReview, &author, Some("other"));
+
+        assert!(matches!(require_transition(&article, ArticleState::Approved, &editor, None), Err(Unauthorized(_))));
+    }
+
+    #[test]
+    fn editors_can_not_approve_own_article() {
+        let editor = user("editor", Role::Editor);
+        let article = article(ArticleState::InReview, &editor, None);
+
+        assert!(matches!(require_transition(&article, ArticleState::Approved, &editor, None), Err(Unauthorized(_))));
+    }
+
+    #[test]
+    fn requesting_changes_needs_a_comment() {
+        let author = user("author", Role::Author);
+        let editor = user("editor", Role::Editor);
+        let article = article(ArticleState::InReview, &author, None);
+
+        assert!(matches!(require_transition(&article, ArticleState::Draft, &editor, None), Err(Validation(_))));
+        assert!(require_transition(&article, ArticleState::Draft, &editor, Some("Fix the intro.")).is_ok());
+    }
+
+    #[test]
+    fn publishing_needs_manage_articles() {
+        let author = user("author", Role::Author);
+        let editor = user("editor", Role::Editor);
+        let article = article(ArticleState::Approved, &author, None);
+
+        assert!(matches!(require_transition(&article, ArticleState::Published, &author, None), Err(Unauthorized(_))));
+        assert!(require_transition(&article, ArticleState::Published, &editor, None).is_ok());
+    }
+
+    #[test]
+    fn publishing_respects_required_totp() {
+        let author = user("author", Role::Author);
+        let mut editor = user("editor", Role::Editor);
+        editor.totp_required = true;
+        let article = article(ArticleState::Approved, &author, None);
+
+        assert!(matches!(require_transition(&article, ArticleState::Published, &editor, None), Err(Forbidden(_))));
+    }
+
+    #[test]
+    fn skipping_states_is_a_conflict() {
+        let author = user("author", Role::Author);
+        let editor = user("editor", Role::Editor);
+
+        assert!(matches!(
+            require_transition(&article(ArticleState::Draft, &author, None), ArticleState::Published, &editor, None),
+            Err(Conflict(_))));
+        assert!(matches!(
+            require_transition(&article(ArticleState::Published, &author, None), ArticleState::InReview, &editor, None),
+            Err(Conflict(_))));
+    }
+}
\ No newline at end of file
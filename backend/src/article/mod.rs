@@ -1,2 +1,7 @@
 pub(crate) mod article_entity;
+pub(crate) mod article_slug;
+pub(crate) mod article_revision;
+pub(crate) mod article_format;
+pub(crate) mod article_search;
+pub(crate) mod article_state;
 pub(crate) mod article_handler;
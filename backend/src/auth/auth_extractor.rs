@@ -0,0 +1,85 @@
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum_extra::headers::Authorization;
+use axum_extra::headers::authorization::Bearer;
+use axum_extra::TypedHeader;
+
+use crate::AppInject;
+use crate::auth::{Authentication, require_authentication};
+use crate::fenster_error::FensterError;
+use crate::fenster_error::FensterError::{NotFound, Unauthorized};
+use crate::user::user_entity;
+use crate::user::user_entity::User;
+use crate::user::user_role::Permission;
+
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub(crate) user: User,
+    pub(crate) session: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MaybeAuthenticatedUser(pub Option<User>);
+
+#[derive(Clone, Debug)]
+pub struct AuthorUser(pub User);
+
+#[async_trait]
+impl FromRequestParts<AppInject> for AuthenticatedUser {
+    type Rejection = FensterError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let authentication = match parts.extensions.get::<Authentication>() {
+            Some(authentication) => authentication.clone(),
+            None => {
+                let TypedHeader(Authorization(bearer)) =
+                    TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state).await
+                        .map_err(|_| Unauthorized("Missing or malformed bearer token.".to_string()))?;
+
+                require_authentication(bearer, state.jwt.as_deref(), state.redis_connection.clone()).await?
+            }
+        };
+
+        let mut user = user_entity::fetch(authentication.user_id.as_str(), &state.postgres_pool).await
+            .map_err(|err| match err {
+                NotFound(_) => Unauthorized(format!("User with given id ({}) does not exist anymore.", authentication.user_id)),
+                err => err,
+            })?;
+
+        user.totp_required = state.backend_config.auth.totp.required_roles.contains(&user.role);
+
+        Ok(AuthenticatedUser {
+            user,
+            session: authentication.session_id,
+        })
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppInject> for MaybeAuthenticatedUser {
+    type Rejection = FensterError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppInject) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key(AUTHORIZATION) {
+            return Ok(MaybeAuthenticatedUser(None));
+        }
+
+        let AuthenticatedUser { user, .. } = AuthenticatedUser::from_request_parts(parts, state).await?;
+        Ok(MaybeAuthenticatedUser(Some(user)))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppInject> for AuthorUser {
+    type Rejection = FensterError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let AuthenticatedUser { user, .. } = AuthenticatedUser::from_request_parts(parts, state).await?;
+
+        user.require_permission(Permission::WriteArticles)?;
+
+        Ok(AuthorUser(user))
+    }
+}
@@ -2,55 +2,136 @@ use anyhow::Result;
 use axum::{Json, Router};
 use axum::extract::State;
 use axum::http::StatusCode;
-use axum::routing::{post, put};
-use axum_extra::headers::Authorization;
-use axum_extra::headers::authorization::Bearer;
+use axum::response::Html;
+use axum::routing::{delete, get, post, put};
+use axum_extra::headers::UserAgent;
 use axum_extra::TypedHeader;
-use tracing::error;
+use redis::aio::MultiplexedConnection;
+use sqlx::PgPool;
+use tracing::{error, warn};
 
-use crate::{AppInject, user::user_entity};
-use crate::auth::{LoginUser, RefreshBody, RegisterUser, require_authentication, token_entity};
-use crate::auth::token_entity::Token;
-use crate::fenster_error::{error, FensterError, OTHER_INTERNAL_ERROR};
-use crate::fenster_error::FensterError::{Conflict, Internal, Unauthorized};
+use crate::{AppInject, html, user::user_entity};
+use crate::backend_config::BackendConfig;
+use crate::client_ip::ClientIp;
+use crate::mail::{mail_template, Mailer};
+use crate::auth::{ForgotPassword, lockout_entity, LoginResponse, LoginUser, MfaLogin, RefreshBody, RegisterUser, ResendVerification, ResetPassword, token_entity, totp_entity, TotpCode, VerifyEmail};
+use crate::auth::auth_extractor::AuthenticatedUser;
+use crate::auth::token_entity::{SessionInfo, Token};
+use crate::auth::totp_entity::{RecoveryCodes, TotpEnrollment};
+use crate::fenster_error::{error, FensterError, FieldError, OTHER_INTERNAL_ERROR};
+use crate::fenster_error::FensterError::{Conflict, Forbidden, Internal, NotFound, Unauthorized, Validation};
 use crate::user::user_entity::User;
+use crate::user::user_role::Role;
+use crate::validation::{PathParams, QueryParams, ValidForm, ValidJson};
 
 pub fn auth_router() -> Router<AppInject> {
     Router::new()
         .route("/login", put(login))
+        .route("/login/mfa", put(login_mfa))
         .route("/logout", put(logout))
         .route("/refresh", put(refresh))
         .route("/register", post(register))
-        .route("/delete", put(delete))
+        .route("/delete", put(delete_user))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route("/verify", get(verify_link).post(verify))
+        .route("/verify/confirm", post(confirm_verify_link))
+        .route("/verify/resend", post(resend_verification))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/:id", delete(revoke_session))
+        .route("/totp/enroll", post(enroll_totp))
+        .route("/totp/confirm", post(confirm_totp))
+        .route("/totp/disable", post(disable_totp))
+        .route("/totp/recovery", post(regenerate_recovery_codes))
 }
 
 pub async fn login(
-    State(AppInject { postgres_pool, redis_connection, .. }): State<AppInject>,
-    Json(login): Json<LoginUser>,
-) -> Result<Json<Token>, FensterError> {
-    let user = user_entity::fetch(login.id.as_str(), &postgres_pool).await?;
+    State(AppInject { postgres_pool, redis_connection, backend_config, jwt, .. }): State<AppInject>,
+    ClientIp(ip): ClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    ValidJson(login): ValidJson<LoginUser>,
+) -> Result<Json<LoginResponse>, FensterError> {
+    let lockout = &backend_config.auth.lockout;
+
+    lockout_entity::require_unlocked(login.id.as_str(), ip, redis_connection.clone()).await?;
+
+    let user = match user_entity::fetch(login.id.as_str(), &postgres_pool).await {
+        Err(NotFound(message)) => {
+            lockout_entity::record_failure(login.id.as_str(), ip, lockout, redis_connection).await?;
+            return Err(NotFound(message));
+        }
+        user => user?,
+    };
 
     if !user.matches(login.password.as_str(), &postgres_pool).await? {
+        lockout_entity::record_failure(login.id.as_str(), ip, lockout, redis_connection).await?;
         return Err(Unauthorized(format!("Password for user with given id ({}) is incorrect.", login.id)));
     }
 
-    let token = token_entity::create_token(user, redis_connection).await?;
+    if !user.verified {
+        return Err(Forbidden(format!("Email address of user with given id ({}) is not verified yet.", login.id)));
+    }
+
+    if user.totp_enabled {
+        let mfa_ticket = token_entity::create_mfa_ticket(user.id.clone(), redis_connection).await?;
+        return Ok(Json(LoginResponse::MfaPending { mfa_ticket }));
+    }
+
+    lockout_entity::clear_failures(user.id.as_str(), redis_connection.clone()).await?;
+
+    let user_agent = user_agent.map(|TypedHeader(user_agent)| user_agent.to_string());
+
+    let token = token_entity::create_token(user, user_agent, jwt.as_deref(), redis_connection).await?;
+    Ok(Json(LoginResponse::Token(token)))
+}
+
+pub async fn login_mfa(
+    State(AppInject { postgres_pool, redis_connection, backend_config, jwt, .. }): State<AppInject>,
+    ClientIp(ip): ClientIp,
+    user_agent: Option<TypedHeader<UserAgent>>,
+    ValidJson(login): ValidJson<MfaLogin>,
+) -> Result<Json<Token>, FensterError> {
+    let user_id = token_entity::mfa_ticket_user(login.mfa_ticket.as_str(), redis_connection.clone()).await?;
+
+    lockout_entity::require_unlocked(user_id.as_str(), ip, redis_connection.clone()).await?;
+
+    let user = user_entity::fetch(user_id.as_str(), &postgres_pool).await?;
+
+    let valid = match (login.code, login.recovery_code) {
+        (Some(code), _) => totp_entity::verify(&user, &backend_config.school, code.as_str(), &postgres_pool).await?,
+        (None, Some(recovery_code)) => totp_entity::consume_recovery_code(&user, recovery_code.as_str(), &postgres_pool).await?,
+        (None, None) => false,
+    };
+
+    if !valid {
+        token_entity::fail_mfa_ticket(login.mfa_ticket.as_str(), redis_connection.clone()).await?;
+        lockout_entity::record_failure(user.id.as_str(), ip, &backend_config.auth.lockout, redis_connection).await?;
+        return Err(Unauthorized(format!("Two-factor code for user with given id ({}) is incorrect.", user.id)));
+    }
+
+    token_entity::consume_mfa_ticket(login.mfa_ticket, redis_connection.clone()).await?;
+    lockout_entity::clear_failures(user.id.as_str(), redis_connection.clone()).await?;
+
+    let user_agent = user_agent.map(|TypedHeader(user_agent)| user_agent.to_string());
+
+    let token = token_entity::create_token(user, user_agent, jwt.as_deref(), redis_connection).await?;
     Ok(Json(token))
 }
 
 pub async fn logout(
     State(AppInject { redis_connection, .. }): State<AppInject>,
-    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    AuthenticatedUser { session, .. }: AuthenticatedUser,
 ) -> Result<StatusCode, FensterError> {
-    require_authentication(bearer.clone(), redis_connection.clone()).await?;
+    if let Some(session) = token_entity::session_from_id(session.as_str(), redis_connection.clone()).await? {
+        token_entity::revoke_session(&session, redis_connection).await?;
+    }
 
-    token_entity::revoke_access(bearer.token().to_string(), redis_connection.clone()).await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn register(
-    State(AppInject { postgres_pool, backend_config, .. }): State<AppInject>,
-    Json(register): Json<RegisterUser>,
+    State(AppInject { postgres_pool, redis_connection, backend_config, mailer, .. }): State<AppInject>,
+    ValidJson(register): ValidJson<RegisterUser>,
 ) -> Result<StatusCode, FensterError> {
     let school = backend_config.school.clone();
 
@@ -61,7 +142,10 @@ pub async fn register(
         .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 1)))?;
 
     if !regex.is_match(register.email.as_str()) {
-        return Err(Conflict(format!("Mail ({}) does not match the pattern (Example {}).", register.email, school.example_mail).to_string()));
+        return Err(Validation(vec![FieldError {
+            field: "email".to_string(),
+            message: format!("Mail ({}) does not match the pattern (Example {}).", register.email, school.example_mail),
+        }]));
     }
 
     if user_entity::exists_id(register.id.as_str(), &postgres_pool).await? {
@@ -76,29 +160,214 @@ pub async fn register(
         id: register.id,
         name: register.name,
         email: register.email,
-        author: false,
+        role: Role::Reader,
+        verified: false,
+        totp_enabled: false,
+        totp_required: false,
     };
 
     user.store(register.password.as_str(), &postgres_pool).await?;
+
+    if send_verification(&user, &backend_config, redis_connection, mailer.as_ref()).await.is_err() {
+        warn!("Verification mail for user with id ({}) could not be sent, it has to be requested again.", user.id);
+    }
+
     Ok(StatusCode::CREATED)
 }
 
 pub async fn refresh(
-    State(AppInject { redis_connection, .. }): State<AppInject>,
-    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
-    Json(refresh): Json<RefreshBody>,
+    State(AppInject { postgres_pool, redis_connection, jwt, .. }): State<AppInject>,
+    ValidJson(refresh): ValidJson<RefreshBody>,
 ) -> Result<Json<Token>, FensterError> {
-    require_authentication(bearer.clone(), redis_connection.clone()).await?;
-    let token = token_entity::refresh_access(refresh.refresh_token, redis_connection.clone()).await?;
+    let token = token_entity::refresh_access(refresh.refresh_token, jwt.as_deref(), &postgres_pool, redis_connection).await?;
     Ok(Json(token))
 }
 
-pub async fn delete(
+pub async fn delete_user(
     State(AppInject { redis_connection, postgres_pool, .. }): State<AppInject>,
-    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
 ) -> Result<StatusCode, FensterError> {
-    let user_id = require_authentication(bearer.clone(), redis_connection.clone()).await?;
-    token_entity::revoke_access(bearer.token().to_string(), redis_connection).await?;
-    user_entity::delete(user_id.as_str(), &postgres_pool).await?;
+    token_entity::revoke_all(user.id.clone(), redis_connection).await?;
+    user_entity::delete(user.id.as_str(), &postgres_pool).await?;
     Ok(StatusCode::OK)
+}
+
+pub async fn forgot_password(
+    State(AppInject { postgres_pool, redis_connection, backend_config, mailer, .. }): State<AppInject>,
+    ValidJson(forgot): ValidJson<ForgotPassword>,
+) -> Result<StatusCode, FensterError> {
+    if !user_entity::exists_mail(forgot.email.as_str(), &postgres_pool).await? {
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    let user = user_entity::fetch_by_mail(forgot.email.as_str(), &postgres_pool).await?;
+    let token = token_entity::create_reset_token(user.id.clone(), redis_connection).await?;
+
+    mailer.send(mail_template::password_reset(&backend_config.school, &user, token.as_str())).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn reset_password(
+    State(AppInject { postgres_pool, redis_connection, .. }): State<AppInject>,
+    ValidJson(reset): ValidJson<ResetPassword>,
+) -> Result<StatusCode, FensterError> {
+    let user_id = token_entity::consume_reset_token(reset.token, redis_connection.clone()).await?;
+
+    user_entity::update_password(user_id.as_str(), reset.password.as_str(), &postgres_pool).await?;
+    token_entity::revoke_all(user_id, redis_connection).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn verify(
+    State(AppInject { postgres_pool, redis_connection, .. }): State<AppInject>,
+    ValidJson(verify): ValidJson<VerifyEmail>,
+) -> Result<StatusCode, FensterError> {
+    let user_id = token_entity::consume_verification_token(verify.token, redis_connection).await?;
+
+    user_entity::set_verified(user_id.as_str(), &postgres_pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn verify_link(
+    State(AppInject { backend_config, .. }): State<AppInject>,
+    QueryParams(verify): QueryParams<VerifyEmail>,
+) -> Html<String> {
+    verification_page(format!(
+        "<p>Please confirm your email address.</p>\n\
+        <form method=\"post\" action=\"{}/auth/verify/confirm\">\n\
+        <input type=\"hidden\" name=\"token\" value=\"{}\">\n\
+        <button type=\"submit\">Confirm</button>\n\
+        </form>",
+        html::escape(backend_config.public_url.as_str()), html::escape(verify.token.as_str())).as_str())
+}
+
+pub async fn confirm_verify_link(
+    State(AppInject { postgres_pool, redis_connection, .. }): State<AppInject>,
+    ValidForm(verify): ValidForm<VerifyEmail>,
+) -> Result<Html<String>, FensterError> {
+    let user_id = token_entity::consume_verification_token(verify.token, redis_connection).await?;
+
+    user_entity::set_verified(user_id.as_str(), &postgres_pool).await?;
+    Ok(verification_page("<p>Your email address has been verified. You can now log in.</p>"))
+}
+
+pub async fn resend_verification(
+    State(AppInject { postgres_pool, redis_connection, backend_config, mailer, .. }): State<AppInject>,
+    ValidJson(resend): ValidJson<ResendVerification>,
+) -> Result<StatusCode, FensterError> {
+    if !user_entity::exists_mail(resend.email.as_str(), &postgres_pool).await? {
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    let user = user_entity::fetch_by_mail(resend.email.as_str(), &postgres_pool).await?;
+
+    if !user.verified {
+        send_verification(&user, &backend_config, redis_connection, mailer.as_ref()).await?;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub async fn list_sessions(
+    State(AppInject { redis_connection, .. }): State<AppInject>,
+    AuthenticatedUser { user, session, .. }: AuthenticatedUser,
+) -> Result<Json<Vec<SessionInfo>>, FensterError> {
+    let mut sessions = token_entity::list_sessions(user.id.as_str(), redis_connection).await?;
+
+    for info in sessions.iter_mut() {
+        info.current = info.id == session;
+    }
+
+    Ok(Json(sessions))
+}
+
+pub async fn revoke_session(
+    State(AppInject { redis_connection, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+) -> Result<StatusCode, FensterError> {
+    let session = token_entity::session_from_id(id.as_str(), redis_connection.clone()).await?
+        .filter(|session| session.user_id == user.id)
+        .ok_or(NotFound(format!("Session with given id ({}) does not exist.", id)))?;
+
+    token_entity::revoke_session(&session, redis_connection).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn enroll_totp(
+    State(AppInject { postgres_pool, backend_config, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+) -> Result<Json<TotpEnrollment>, FensterError> {
+    let enrollment = totp_entity::enroll(&user, &backend_config.school, &postgres_pool).await?;
+    Ok(Json(enrollment))
+}
+
+pub async fn confirm_totp(
+    State(AppInject { postgres_pool, backend_config, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    ValidJson(confirm): ValidJson<TotpCode>,
+) -> Result<Json<RecoveryCodes>, FensterError> {
+    if user.totp_enabled {
+        return Err(Conflict(format!("User with given id ({}) already has two-factor authentication enabled.", user.id)));
+    }
+
+    if !totp_entity::confirm(&user, &backend_config.school, confirm.code.as_str(), &postgres_pool).await? {
+        return Err(Unauthorized(format!("Two-factor code for user with given id ({}) is incorrect.", user.id)));
+    }
+
+    let recovery_codes = totp_entity::create_recovery_codes(&user, &postgres_pool).await?;
+    Ok(Json(recovery_codes))
+}
+
+pub async fn disable_totp(
+    State(AppInject { postgres_pool, backend_config, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    ValidJson(disable): ValidJson<TotpCode>,
+) -> Result<StatusCode, FensterError> {
+    require_totp_code(&user, &backend_config, disable.code.as_str(), &postgres_pool).await?;
+
+    totp_entity::disable(&user, &postgres_pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn regenerate_recovery_codes(
+    State(AppInject { postgres_pool, backend_config, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    ValidJson(regenerate): ValidJson<TotpCode>,
+) -> Result<Json<RecoveryCodes>, FensterError> {
+    require_totp_code(&user, &backend_config, regenerate.code.as_str(), &postgres_pool).await?;
+
+    let recovery_codes = totp_entity::create_recovery_codes(&user, &postgres_pool).await?;
+    Ok(Json(recovery_codes))
+}
+
+async fn require_totp_code(user: &User, backend_config: &BackendConfig, code: &str, pool: &PgPool) -> Result<(), FensterError> {
+    if !user.totp_enabled {
+        return Err(Conflict(format!("User with given id ({}) has no two-factor authentication enabled.", user.id)));
+    }
+
+    if !totp_entity::verify(user, &backend_config.school, code, pool).await? {
+        return Err(Unauthorized(format!("Two-factor code for user with given id ({}) is incorrect.", user.id)));
+    }
+
+    Ok(())
+}
+
+fn verification_page(body: &str) -> Html<String> {
+    Html(format!("<!DOCTYPE html>\n<html>\n<body>\n{}\n</body>\n</html>", body))
+}
+
+async fn send_verification(
+    user: &User,
+    backend_config: &BackendConfig,
+    redis: MultiplexedConnection,
+    mailer: &dyn Mailer,
+) -> Result<(), FensterError> {
+    let token = token_entity::create_verification_token(user.id.clone(), redis).await?;
+
+    let link = format!("{}/auth/verify?token={}", backend_config.public_url, token);
+
+    mailer.send(mail_template::verification(&backend_config.school, user, link.as_str())).await
 }
\ No newline at end of file
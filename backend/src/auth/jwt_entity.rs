@@ -0,0 +1,111 @@
+use std::fs;
+use std::time::Duration;
+
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::auth::token_entity::{AccessToken, get_sys_time};
+use crate::backend_config::{Jwt, JwtAlgorithm};
+use crate::fenster_error::{error, FensterError, OTHER_INTERNAL_ERROR};
+use crate::fenster_error::FensterError::{Internal, Unauthorized};
+use crate::user::user_entity::User;
+use crate::user::user_role::Role;
+
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    expiration: Duration,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub sid: String,
+    pub role: Role,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+pub fn create_jwt_keys(jwt: Jwt) -> Result<JwtKeys, FensterError> {
+    let (algorithm, encoding, decoding) = match jwt.algorithm {
+        JwtAlgorithm::Hs256 => {
+            let secret = jwt.secret.ok_or_else(|| {
+                error!("Jwt algorithm hs256 requires a secret.");
+                Internal(error(OTHER_INTERNAL_ERROR, 3))
+            })?;
+
+            (Algorithm::HS256, EncodingKey::from_secret(secret.as_bytes()), DecodingKey::from_secret(secret.as_bytes()))
+        }
+        JwtAlgorithm::EdDsa => {
+            let private_key = read_key(jwt.private_key)?;
+            let public_key = read_key(jwt.public_key)?;
+
+            let encoding = EncodingKey::from_ed_pem(private_key.as_slice())
+                .inspect_err(|err| {
+                    error!("Error while parsing eddsa private key. - {}", err)
+                })
+                .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 8)))?;
+
+            let decoding = DecodingKey::from_ed_pem(public_key.as_slice())
+                .inspect_err(|err| {
+                    error!("Error while parsing eddsa public key. - {}", err)
+                })
+                .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 9)))?;
+
+            (Algorithm::EdDSA, encoding, decoding)
+        }
+    };
+
+    Ok(JwtKeys {
+        algorithm,
+        encoding,
+        decoding,
+        expiration: Duration::from_secs(60 * jwt.expiration_minutes),
+    })
+}
+
+pub fn encode(keys: &JwtKeys, user: &User, session_id: &str) -> Result<AccessToken, FensterError> {
+    let sys_time = get_sys_time()?;
+    let expiration = sys_time + keys.expiration;
+
+    let claims = Claims {
+        sub: user.id.clone(),
+        sid: session_id.to_string(),
+        role: user.role,
+        iat: sys_time.as_secs(),
+        exp: expiration.as_secs(),
+    };
+
+    let token = jsonwebtoken::encode(&Header::new(keys.algorithm), &claims, &keys.encoding)
+        .inspect_err(|err| {
+            error!("Error while encoding jwt for user_id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 4)))?;
+
+    Ok(AccessToken {
+        token,
+        expiration_period: Duration::from_secs(claims.exp).as_millis(),
+    })
+}
+
+pub fn decode(keys: &JwtKeys, token: &str) -> Result<Claims, FensterError> {
+    let data = jsonwebtoken::decode::<Claims>(token, &keys.decoding, &Validation::new(keys.algorithm))
+        .map_err(|err| Unauthorized(format!("Token is invalid. - {}", err)))?;
+
+    Ok(data.claims)
+}
+
+fn read_key(path: Option<String>) -> Result<Vec<u8>, FensterError> {
+    let path = path.ok_or_else(|| {
+        error!("Jwt algorithm eddsa requires a private_key and a public_key.");
+        Internal(error(OTHER_INTERNAL_ERROR, 10))
+    })?;
+
+    fs::read(path.as_str())
+        .inspect_err(|err| {
+            error!("Error while reading jwt key ({}). - {}", path, err)
+        })
+        .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 11)))
+}
@@ -0,0 +1,169 @@
+use std::net::IpAddr;
+
+use anyhow::Result;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use tracing::{error, warn};
+
+use crate::backend_config::Lockout;
+use crate::fenster_error::{error, FensterError, REDIS_ERROR};
+use crate::fenster_error::FensterError::{Internal, TooManyRequests};
+
+const USER_FAILURES_PREFIX: &str = "login_failures:user:";
+const IP_FAILURES_PREFIX: &str = "login_failures:ip:";
+const USER_LOCK_PREFIX: &str = "login_lock:user:";
+const IP_LOCK_PREFIX: &str = "login_lock:ip:";
+const USER_IPS_PREFIX: &str = "login_failure_ips:user:";
+
+pub async fn require_unlocked(user_id: &str, ip: IpAddr, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    let (user_lock, ip_lock): (i64, i64) = redis::pipe()
+        .pttl(format!("{USER_LOCK_PREFIX}{user_id}"))
+        .pttl(format!("{IP_LOCK_PREFIX}{ip}"))
+        .query_async(&mut redis).await
+        .inspect_err(|err| {
+            error!("Unable to read login locks for user_id ({}) and ip ({}) - {}", user_id, ip, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 16)))?;
+
+    let remaining = user_lock.max(ip_lock);
+
+    if remaining <= 0 {
+        return Ok(());
+    }
+
+    let retry_after = (remaining as u64).div_ceil(1000);
+
+    Err(TooManyRequests(
+        format!("Too many failed logins for user with given id ({}). Try again in {} seconds.", user_id, retry_after),
+        retry_after,
+    ))
+}
+
+pub async fn record_failure(
+    user_id: &str,
+    ip: IpAddr,
+    lockout: &Lockout,
+    mut redis: MultiplexedConnection,
+) -> Result<(), FensterError> {
+    let user_failures = format!("{USER_FAILURES_PREFIX}{user_id}");
+    let ip_failures = format!("{IP_FAILURES_PREFIX}{ip}");
+    let user_ips = format!("{USER_IPS_PREFIX}{user_id}");
+
+    let (user_count, _, ip_count, _): (u64, (), u64, ()) = redis::pipe()
+        .atomic()
+        .incr(&user_failures, 1)
+        .expire(&user_failures, lockout.window_seconds as i64)
+        .incr(&ip_failures, 1)
+        .expire(&ip_failures, lockout.window_seconds as i64)
+        .sadd(&user_ips, ip.to_string()).ignore()
+        .expire(&user_ips, lockout.max_delay_seconds.max(lockout.window_seconds) as i64).ignore()
+        .query_async(&mut redis).await
+        .inspect_err(|err| {
+            error!("Unable to count failed login for user_id ({}) and ip ({}) - {}", user_id, ip, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 17)))?;
+
+    if let Some(delay) = lock_delay(user_count, lockout) {
+        warn!("Locking logins for user_id ({}) for {} seconds after {} failed attempts.", user_id, delay, user_count);
+        lock(format!("{USER_LOCK_PREFIX}{user_id}"), delay, redis.clone()).await?;
+    }
+
+    if let Some(delay) = lock_delay(ip_count, lockout) {
+        warn!("Locking logins from ip ({}) for {} seconds after {} failed attempts.", ip, delay, ip_count);
+        lock(format!("{IP_LOCK_PREFIX}{ip}"), delay, redis).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn clear_failures(user_id: &str, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    redis.del::<_, ()>(&[
+        format!("{USER_FAILURES_PREFIX}{user_id}"),
+        format!("{USER_LOCK_PREFIX}{user_id}"),
+    ]).await
+        .inspect_err(|err| {
+            error!("Unable to clear failed logins for user_id ({}) - {}", user_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 18)))
+}
+
+/// Lifts the lockout of a user, including the ip locks their failed logins caused.
+pub async fn unlock(user_id: &str, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    let user_ips = format!("{USER_IPS_PREFIX}{user_id}");
+
+    let ips: Vec<String> = redis.smembers(&user_ips).await
+        .inspect_err(|err| {
+            error!("Unable to read failed login ips of user_id ({}) - {}", user_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 24)))?;
+
+    let mut keys = vec![
+        user_ips,
+        format!("{USER_FAILURES_PREFIX}{user_id}"),
+        format!("{USER_LOCK_PREFIX}{user_id}"),
+    ];
+
+    for ip in ips {
+        keys.push(format!("{IP_FAILURES_PREFIX}{ip}"));
+        keys.push(format!("{IP_LOCK_PREFIX}{ip}"));
+    }
+
+    redis.del::<_, ()>(&keys).await
+        .inspect_err(|err| {
+            error!("Unable to unlock logins of user_id ({}) - {}", user_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 25)))
+}
+
+async fn lock(key: String, seconds: u64, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    redis.set_ex::<_, _, ()>(&key, true, seconds).await
+        .inspect_err(|err| {
+            error!("Unable to set login lock ({}) - {}", key, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 19)))
+}
+
+fn lock_delay(failures: u64, lockout: &Lockout) -> Option<u64> {
+    if failures < lockout.max_attempts {
+        return None;
+    }
+
+    let exponent = (failures - lockout.max_attempts).min(32) as u32;
+
+    Some(lockout.base_delay_seconds
+        .saturating_mul(2u64.saturating_pow(exponent))
+        .min(lockout.max_delay_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lockout() -> Lockout {
+        Lockout {
+            max_attempts: 3,
+            window_seconds: 900,
+            base_delay_seconds: 10,
+            max_delay_seconds: 300,
+        }
+    }
+
+    #[test]
+    fn no_delay_below_max_attempts() {
+        assert_eq!(lock_delay(0, &lockout()), None);
+        assert_eq!(lock_delay(2, &lockout()), None);
+    }
+
+    #[test]
+    fn delay_doubles_per_failure() {
+        assert_eq!(lock_delay(3, &lockout()), Some(10));
+        assert_eq!(lock_delay(4, &lockout()), Some(20));
+        assert_eq!(lock_delay(5, &lockout()), Some(40));
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        assert_eq!(lock_delay(8, &lockout()), Some(300));
+        assert_eq!(lock_delay(u64::MAX, &lockout()), Some(300));
+    }
+}
\ No newline at end of file
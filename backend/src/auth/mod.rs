@@ -1,11 +1,19 @@
 use axum_extra::headers::authorization::Bearer;
 use redis::aio::MultiplexedConnection;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use crate::auth::jwt_entity::JwtKeys;
+use crate::auth::token_entity::Token;
+use crate::backend_config::BackendConfig;
 use crate::fenster_error::FensterError;
+use crate::validation::{Validate, Validator};
 
 pub(crate) mod token_entity;
+pub(crate) mod jwt_entity;
+pub(crate) mod totp_entity;
+pub(crate) mod lockout_entity;
 pub(crate) mod auth_handler;
+pub(crate) mod auth_extractor;
 
 #[derive(Deserialize)]
 pub struct RegisterUser {
@@ -21,18 +29,151 @@ pub struct LoginUser {
     pub(crate) password: String,
 }
 
-pub async fn require_authentication(bearer: Bearer, redis: MultiplexedConnection) -> Result<String, FensterError> {
+#[derive(Clone, Debug)]
+pub struct Authentication {
+    pub(crate) user_id: String,
+    pub(crate) session_id: String,
+}
+
+pub async fn require_authentication(
+    bearer: Bearer,
+    jwt: Option<&JwtKeys>,
+    redis: MultiplexedConnection,
+) -> Result<Authentication, FensterError> {
     let access_token = String::from(bearer.token());
-    
-    let user_id = token_entity::user_id_from_token(access_token, redis.clone()).await?;
 
-    let token = token_entity::token_from_user_id(user_id.clone(), redis.clone()).await?;
+    // Jwt access tokens are verified by signature and expiration only. A revoked session keeps its
+    // access token until it expires and the session's last seen is only updated on login and refresh.
+    if let Some(keys) = jwt {
+        let claims = jwt_entity::decode(keys, access_token.as_str())?;
+
+        return Ok(Authentication {
+            user_id: claims.sub,
+            session_id: claims.sid,
+        });
+    }
 
-    token.auth_token.is_expired()?;
-    Ok(user_id)
+    let session = token_entity::session_from_auth_token(access_token, redis.clone()).await?;
+
+    session.auth_token.is_expired()?;
+    token_entity::touch_session(&session, redis).await?;
+
+    Ok(Authentication {
+        user_id: session.user_id,
+        session_id: session.id,
+    })
 }
 
 #[derive(Deserialize)]
 pub struct RefreshBody {
     pub(crate) refresh_token: String
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPassword {
+    pub(crate) email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPassword {
+    pub(crate) token: String,
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmail {
+    pub(crate) token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResendVerification {
+    pub(crate) email: String,
+}
+
+#[derive(Deserialize)]
+pub struct TotpCode {
+    pub(crate) code: String,
+}
+
+#[derive(Deserialize)]
+pub struct MfaLogin {
+    pub(crate) mfa_ticket: String,
+    pub(crate) code: Option<String>,
+    pub(crate) recovery_code: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Token(Token),
+    MfaPending {
+        mfa_ticket: String,
+    },
+}
+
+impl Validate for RegisterUser {
+    fn validate(&self, config: &BackendConfig, validator: &mut Validator) {
+        validator.field("id", &self.id).id();
+        validator.field("name", &self.name).length(1, 64);
+        validator.field("email", &self.email).email();
+        validator.field("password", &self.password).password(&config.auth.password);
+    }
+}
+
+impl Validate for LoginUser {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("id", &self.id).length(1, 64);
+        validator.field("password", &self.password).length(1, 256);
+    }
+}
+
+impl Validate for RefreshBody {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("refresh_token", &self.refresh_token).token();
+    }
+}
+
+impl Validate for ForgotPassword {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("email", &self.email).email();
+    }
+}
+
+impl Validate for ResetPassword {
+    fn validate(&self, config: &BackendConfig, validator: &mut Validator) {
+        validator.field("token", &self.token).token();
+        validator.field("password", &self.password).password(&config.auth.password);
+    }
+}
+
+impl Validate for VerifyEmail {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("token", &self.token).token();
+    }
+}
+
+impl Validate for ResendVerification {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("email", &self.email).email();
+    }
+}
+
+impl Validate for TotpCode {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("code", &self.code).token();
+    }
+}
+
+impl Validate for MfaLogin {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("mfa_ticket", &self.mfa_ticket).token();
+
+        if let Some(code) = &self.code {
+            validator.field("code", code).token();
+        }
+
+        if let Some(recovery_code) = &self.recovery_code {
+            validator.field("recovery_code", recovery_code).token();
+        }
+    }
 }
\ No newline at end of file
@@ -1,21 +1,44 @@
-use std::iter;
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::string::String;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
-use rand::Rng;
+use rand::RngCore;
+use rand::rngs::OsRng;
 use redis::aio::MultiplexedConnection;
 use redis::AsyncCommands;
 use redis_macros::{FromRedisValue, ToRedisArgs};
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use sqlx::PgPool;
+use sha2::{Digest, Sha256};
+use tracing::{error, warn};
 
 use crate::fenster_error::{error, FensterError, OTHER_INTERNAL_ERROR, REDIS_ERROR};
 use crate::fenster_error::FensterError::{Internal, Unauthorized};
+use crate::auth::jwt_entity;
+use crate::auth::jwt_entity::JwtKeys;
+use crate::user::user_entity;
 use crate::user::user_entity::User;
 
-const CHARS: &str =
-    "1234567890abcdefghijklmopqrstuvwxyzABCDEFGHIJKLMOPQRSTUVWXYZ";
+const AUTH_TOKEN_DAYS: u64 = 7;
+const REFRESH_TOKEN_DAYS: u64 = 14;
+const TOKEN_BYTES: usize = 32;
+
+const AUTH_TOKEN_PREFIX: &str = "auth_token:";
+const REFRESH_TOKEN_PREFIX: &str = "refresh_token:";
+const ROTATED_TOKEN_PREFIX: &str = "rotated_refresh_token:";
+const SESSION_PREFIX: &str = "session:";
+const SESSIONS_PREFIX: &str = "sessions:";
+
+const RESET_TOKEN_PREFIX: &str = "password_reset:";
+const RESET_TOKEN_SECONDS: u64 = 60 * 60;
+const VERIFICATION_TOKEN_PREFIX: &str = "email_verification:";
+const VERIFICATION_TOKEN_SECONDS: u64 = 60 * 60 * 24;
+const MFA_TICKET_PREFIX: &str = "mfa_ticket:";
+const MFA_TICKET_SECONDS: u64 = 60 * 5;
+const MFA_ATTEMPTS_PREFIX: &str = "mfa_attempts:";
+const MFA_TICKET_ATTEMPTS: u64 = 5;
 
 #[derive(Clone, Debug, Serialize, Deserialize, FromRedisValue, ToRedisArgs)]
 pub struct Token {
@@ -29,114 +52,424 @@ pub struct AccessToken {
     pub expiration_period: u128,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, FromRedisValue, ToRedisArgs)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub auth_token: StoredToken,
+    pub refresh_token: StoredToken,
+    pub user_agent: Option<String>,
+    pub created: u128,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub hash: String,
+    pub expiration_period: u128,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub created: u128,
+    pub last_seen: u128,
+    pub current: bool,
+}
+
 impl AccessToken {
+    fn stored(&self) -> StoredToken {
+        StoredToken {
+            hash: hash_token(self.token.as_str()),
+            expiration_period: self.expiration_period,
+        }
+    }
+}
+
+impl StoredToken {
     pub fn is_expired(&self) -> Result<(), FensterError> {
         let sys_time = get_sys_time()?;
 
         if sys_time.as_millis() >= self.expiration_period {
-            return Err(Unauthorized(format!("{} token is expired.", self.token)));
+            return Err(Unauthorized("Token is expired.".to_string()));
         }
 
         Ok(())
     }
 }
 
-pub async fn create_token(user: User, mut redis: MultiplexedConnection) -> Result<Token, FensterError> {
+pub async fn create_token(
+    user: User,
+    user_agent: Option<String>,
+    jwt: Option<&JwtKeys>,
+    mut redis: MultiplexedConnection,
+) -> Result<Token, FensterError> {
+    let session_id = generate_token();
+
     let token = Token {
-        auth_token: token_with_expiration(7)?,
-        refresh_token: token_with_expiration(14)?,
+        auth_token: issue_auth_token(jwt, &user, session_id.as_str())?,
+        refresh_token: token_with_expiration(REFRESH_TOKEN_DAYS)?,
     };
 
-    redis.set(user.clone().id, token.clone()).await
+    let session = Session {
+        id: session_id,
+        user_id: user.id.clone(),
+        auth_token: token.auth_token.stored(),
+        refresh_token: token.refresh_token.stored(),
+        user_agent,
+        created: get_sys_time()?.as_millis(),
+    };
+
+    let session_key = session_key(session.id.as_str());
+    let auth_key = auth_key(session.auth_token.hash.as_str());
+    let refresh_key = refresh_key(session.refresh_token.hash.as_str());
+
+    let mut pipe = redis::pipe();
+
+    pipe.atomic()
+        .set(&session_key, session.clone()).ignore()
+        .pexpire_at(&session_key, expire_at(session.refresh_token.expiration_period)).ignore()
+        .set(&refresh_key, session.id.clone()).ignore()
+        .pexpire_at(&refresh_key, expire_at(session.refresh_token.expiration_period)).ignore();
+
+    if jwt.is_none() {
+        pipe.set(&auth_key, session.id.clone()).ignore()
+            .pexpire_at(&auth_key, expire_at(session.auth_token.expiration_period)).ignore();
+    }
+
+    pipe.query_async::<_, ()>(&mut redis).await
         .inspect_err(|err| {
-            error!("Unable to set token to user_id ({}). - {}", user.clone().id, err)
+            error!("Unable to set session and auth/refresh _token to user_id ({}). - {}", user.id, err)
         })
         .map_err(|_| Internal(error(REDIS_ERROR, 1)))?;
-    
-    redis.mset(&[
-        (token.clone().auth_token.token, user.clone().id),
-        (token.clone().refresh_token.token, user.clone().id)
-    ]).await
-        .inspect_err(|err| {
-            error!("Unable to set auth/refresh _token to user_id ({}). - {}", user.clone().id, err)
-        })
-        .map_err(|_| Internal(error(REDIS_ERROR, 2)))?;
+
+    touch_session(&session, redis).await?;
 
     Ok(token)
 }
 
-pub async fn user_id_from_token(token: String, mut redis: MultiplexedConnection) -> Result<String, FensterError> {
-    let user_id = redis.get(token.clone()).await
+pub async fn session_from_auth_token(token: String, redis: MultiplexedConnection) -> Result<Session, FensterError> {
+    session_from_key(auth_key(hash_token(token.as_str()).as_str()), redis).await
+}
+
+pub async fn session_from_id(session_id: &str, mut redis: MultiplexedConnection) -> Result<Option<Session>, FensterError> {
+    redis.get(session_key(session_id)).await
         .inspect_err(|err| {
-            error!("Unable to get user_id from token ({token}) - {}", err)
+            error!("Unable to get session ({}) - {}", session_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 5)))
+}
+
+async fn session_from_key(key: String, mut redis: MultiplexedConnection) -> Result<Session, FensterError> {
+    let session_id: Option<String> = redis.get(key.clone()).await
+        .inspect_err(|err| {
+            error!("Unable to get session from token - {}", err)
         })
         .map_err(|_| Internal(error(REDIS_ERROR, 3)))?;
 
-    Ok(user_id)
+    let Some(session_id) = session_id else {
+        return Err(Unauthorized("Token is invalid.".to_string()));
+    };
+
+    session_from_id(session_id.as_str(), redis).await?
+        .ok_or(Unauthorized("Session of token does not exist anymore.".to_string()))
 }
 
-pub async fn token_from_user_id(user_id: String, mut redis: MultiplexedConnection) -> Result<Token, FensterError> {
-    let token: Token = redis.get(user_id.clone()).await
+pub async fn touch_session(session: &Session, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    let sessions_key = sessions_key(session.user_id.as_str());
+
+    redis::pipe()
+        .hset(&sessions_key, session.id.clone(), get_sys_time()?.as_millis().to_string()).ignore()
+        .expire(&sessions_key, (60 * 60 * 24 * REFRESH_TOKEN_DAYS) as i64).ignore()
+        .query_async::<_, ()>(&mut redis).await
         .inspect_err(|err| {
-            error!("Unable to get token from user_id ({}) - {}", user_id.clone(), err)
+            error!("Unable to update last seen of session ({}) - {}", session.id, err)
         })
-        .map_err(|_| Internal(error(REDIS_ERROR, 5)))?;
-
-    Ok(token)
+        .map_err(|_| Internal(error(REDIS_ERROR, 4)))
 }
 
-pub async fn refresh_access(token: String, mut redis: MultiplexedConnection) -> Result<Token, FensterError> {
-    let user_id = user_id_from_token(token, redis.clone()).await?;
+pub async fn list_sessions(user_id: &str, mut redis: MultiplexedConnection) -> Result<Vec<SessionInfo>, FensterError> {
+    let last_seen: HashMap<String, String> = redis.hgetall(sessions_key(user_id)).await
+        .inspect_err(|err| {
+            error!("Unable to get sessions of user_id ({}) - {}", user_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 15)))?;
+
+    let mut sessions = Vec::new();
+
+    for (session_id, last_seen) in last_seen {
+        let Some(session) = session_from_id(session_id.as_str(), redis.clone()).await? else {
+            forget_session(user_id, session_id.as_str(), redis.clone()).await?;
+            continue;
+        };
+
+        sessions.push(SessionInfo {
+            id: session.id,
+            user_agent: session.user_agent,
+            created: session.created,
+            last_seen: last_seen.parse().unwrap_or(session.created),
+            current: false,
+        });
+    }
 
-    let token = token_from_user_id(user_id.clone(), redis.clone()).await?;
+    sessions.sort_by_key(|session| Reverse(session.last_seen));
+    Ok(sessions)
+}
 
-    redis.del(token.clone().auth_token.token).await
+pub async fn refresh_access(
+    token: String,
+    jwt: Option<&JwtKeys>,
+    pool: &PgPool,
+    mut redis: MultiplexedConnection,
+) -> Result<Token, FensterError> {
+    let refresh_hash = hash_token(token.as_str());
+
+    let session_id: Option<String> = redis.get_del(refresh_key(refresh_hash.as_str())).await
         .inspect_err(|err| {
-            error!("Unable to delete user_id from auth_token ({}) - {}", token.clone().auth_token.token, err)
+            error!("Unable to claim refresh_token - {}", err)
         })
         .map_err(|_| Internal(error(REDIS_ERROR, 6)))?;
 
-    let access_token = token_with_expiration(7)?;
+    let Some(session_id) = session_id else {
+        return Err(detect_reuse(refresh_hash.as_str(), redis).await?);
+    };
+
+    let Some(mut session) = session_from_id(session_id.as_str(), redis.clone()).await? else {
+        return Err(Unauthorized("Session of token does not exist anymore.".to_string()));
+    };
+
+    session.refresh_token.is_expired()?;
+
+    let rotated = session.refresh_token.clone();
+
+    let auth_token = match jwt {
+        Some(keys) => {
+            let user = user_entity::fetch(session.user_id.as_str(), pool).await?;
+            jwt_entity::encode(keys, &user, session.id.as_str())?
+        }
+        None => token_with_expiration(AUTH_TOKEN_DAYS)?,
+    };
+
+    let token = Token {
+        auth_token,
+        refresh_token: token_with_expiration(REFRESH_TOKEN_DAYS)?,
+    };
+
+    let old_auth_key = auth_key(session.auth_token.hash.as_str());
+
+    session.auth_token = token.auth_token.stored();
+    session.refresh_token = token.refresh_token.stored();
+
+    let session_key = session_key(session.id.as_str());
+    let auth_key = auth_key(session.auth_token.hash.as_str());
+    let refresh_key = refresh_key(session.refresh_token.hash.as_str());
+    let rotated_key = rotated_key(rotated.hash.as_str());
+
+    let mut pipe = redis::pipe();
+
+    pipe.atomic()
+        .del(&old_auth_key).ignore()
+        .set(&rotated_key, session.id.clone()).ignore()
+        .pexpire_at(&rotated_key, expire_at(rotated.expiration_period)).ignore()
+        .set(&refresh_key, session.id.clone()).ignore()
+        .pexpire_at(&refresh_key, expire_at(session.refresh_token.expiration_period)).ignore()
+        .set(&session_key, session.clone()).ignore()
+        .pexpire_at(&session_key, expire_at(session.refresh_token.expiration_period)).ignore();
 
-    redis.set(access_token.clone().token, user_id.clone()).await
+    if jwt.is_none() {
+        pipe.set(&auth_key, session.id.clone()).ignore()
+            .pexpire_at(&auth_key, expire_at(session.auth_token.expiration_period)).ignore();
+    }
+
+    pipe.query_async::<_, ()>(&mut redis).await
         .inspect_err(|err| {
-            error!("Unable to set user_id ({}) to auth_token ({}) - {}", user_id, token.clone().auth_token.token, err)
+            error!("Unable to rotate tokens of session ({}) - {}", session.id, err)
         })
         .map_err(|_| Internal(error(REDIS_ERROR, 7)))?;
 
-    Ok(Token {
-        auth_token: access_token.clone(),
-        refresh_token: token.refresh_token,
-    })
-}
+    touch_session(&session, redis).await?;
 
-pub async fn revoke_access(access_token: String, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
-    
-    let user_id = user_id_from_token(access_token.clone(), redis.clone()).await?;
-
-    let token = token_from_user_id(user_id.clone(), redis.clone()).await?;
+    Ok(token)
+}
 
-    redis.del(token.clone().auth_token.token).await
+async fn detect_reuse(refresh_hash: &str, mut redis: MultiplexedConnection) -> Result<FensterError, FensterError> {
+    let session_id: Option<String> = redis.get(rotated_key(refresh_hash)).await
         .inspect_err(|err| {
-            error!("Unable to delete user_id from auth_token ({}) - {}", token.clone().auth_token.token, err)
+            error!("Unable to look up rotated refresh_token - {}", err)
         })
         .map_err(|_| Internal(error(REDIS_ERROR, 8)))?;
 
-    redis.del(token.clone().refresh_token.token).await
+    let Some(session_id) = session_id else {
+        return Ok(Unauthorized("Token is invalid.".to_string()));
+    };
+
+    if let Some(session) = session_from_id(session_id.as_str(), redis.clone()).await? {
+        warn!("Rotated refresh_token of session ({}) was reused, revoking the session.", session.id);
+        revoke_session(&session, redis).await?;
+    }
+
+    Ok(Unauthorized("Refresh token was already used. All tokens of its session have been revoked.".to_string()))
+}
+
+pub async fn revoke_session(session: &Session, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    redis.del::<_, ()>(&[
+        auth_key(session.auth_token.hash.as_str()),
+        refresh_key(session.refresh_token.hash.as_str()),
+        session_key(session.id.as_str()),
+    ]).await
         .inspect_err(|err| {
-            error!("Unable to set user_id from refresh_token ({}) - {}", token.clone().auth_token.token, err)
+            error!("Unable to delete tokens of session ({}) - {}", session.id, err)
         })
         .map_err(|_| Internal(error(REDIS_ERROR, 9)))?;
 
-    redis.del(user_id.clone()).await
+    forget_session(session.user_id.as_str(), session.id.as_str(), redis).await
+}
+
+pub async fn revoke_all(user_id: String, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    let session_ids: Vec<String> = redis.hkeys(sessions_key(user_id.as_str())).await
         .inspect_err(|err| {
-            error!("Unable to delete access_token from user_id ({}) - {}", user_id.clone(), err)
+            error!("Unable to get sessions of user_id ({}) - {}", user_id, err)
         })
-        .map_err(|_| Internal(error(REDIS_ERROR, 10)))?;
+        .map_err(|_| Internal(error(REDIS_ERROR, 11)))?;
+
+    for session_id in session_ids {
+        match session_from_id(session_id.as_str(), redis.clone()).await? {
+            Some(session) => revoke_session(&session, redis.clone()).await?,
+            None => forget_session(user_id.as_str(), session_id.as_str(), redis.clone()).await?,
+        }
+    }
 
     Ok(())
 }
 
+async fn forget_session(user_id: &str, session_id: &str, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    redis.hdel::<_, _, ()>(sessions_key(user_id), session_id).await
+        .inspect_err(|err| {
+            error!("Unable to remove session ({}) from user_id ({}) - {}", session_id, user_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 10)))
+}
+
+fn auth_key(token: &str) -> String {
+    format!("{AUTH_TOKEN_PREFIX}{token}")
+}
+
+fn refresh_key(token: &str) -> String {
+    format!("{REFRESH_TOKEN_PREFIX}{token}")
+}
+
+fn rotated_key(token: &str) -> String {
+    format!("{ROTATED_TOKEN_PREFIX}{token}")
+}
+
+fn session_key(session_id: &str) -> String {
+    format!("{SESSION_PREFIX}{session_id}")
+}
+
+fn sessions_key(user_id: &str) -> String {
+    format!("{SESSIONS_PREFIX}{user_id}")
+}
+
+pub async fn create_reset_token(user_id: String, redis: MultiplexedConnection) -> Result<String, FensterError> {
+    create_one_time_token(RESET_TOKEN_PREFIX, RESET_TOKEN_SECONDS, user_id, redis).await
+}
+
+pub async fn consume_reset_token(token: String, redis: MultiplexedConnection) -> Result<String, FensterError> {
+    consume_one_time_token(RESET_TOKEN_PREFIX, token, redis).await?
+        .ok_or(Unauthorized("Reset token is invalid or expired.".to_string()))
+}
+
+pub async fn create_verification_token(user_id: String, redis: MultiplexedConnection) -> Result<String, FensterError> {
+    create_one_time_token(VERIFICATION_TOKEN_PREFIX, VERIFICATION_TOKEN_SECONDS, user_id, redis).await
+}
+
+pub async fn consume_verification_token(token: String, redis: MultiplexedConnection) -> Result<String, FensterError> {
+    consume_one_time_token(VERIFICATION_TOKEN_PREFIX, token, redis).await?
+        .ok_or(Unauthorized("Verification token is invalid or expired.".to_string()))
+}
+
+pub async fn create_mfa_ticket(user_id: String, redis: MultiplexedConnection) -> Result<String, FensterError> {
+    create_one_time_token(MFA_TICKET_PREFIX, MFA_TICKET_SECONDS, user_id, redis).await
+}
+
+pub async fn mfa_ticket_user(ticket: &str, mut redis: MultiplexedConnection) -> Result<String, FensterError> {
+    let user_id: Option<String> = redis.get(format!("{MFA_TICKET_PREFIX}{}", hash_token(ticket))).await
+        .inspect_err(|err| {
+            error!("Unable to get user_id from mfa ticket - {}", err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 21)))?;
+
+    user_id.ok_or(Unauthorized("Mfa ticket is invalid or expired.".to_string()))
+}
+
+pub async fn consume_mfa_ticket(ticket: String, redis: MultiplexedConnection) -> Result<String, FensterError> {
+    consume_one_time_token(MFA_TICKET_PREFIX, ticket, redis).await?
+        .ok_or(Unauthorized("Mfa ticket is invalid or expired.".to_string()))
+}
+
+pub async fn fail_mfa_ticket(ticket: &str, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    let ticket_key = format!("{MFA_TICKET_PREFIX}{}", hash_token(ticket));
+    let attempts_key = format!("{MFA_ATTEMPTS_PREFIX}{}", hash_token(ticket));
+
+    let (attempts,): (u64,) = redis::pipe()
+        .atomic()
+        .incr(&attempts_key, 1)
+        .expire(&attempts_key, MFA_TICKET_SECONDS as i64).ignore()
+        .query_async(&mut redis).await
+        .inspect_err(|err| {
+            error!("Unable to count failed attempts of mfa ticket - {}", err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 22)))?;
+
+    if attempts < MFA_TICKET_ATTEMPTS {
+        return Ok(());
+    }
+
+    redis.del::<_, ()>(&[ticket_key, attempts_key]).await
+        .inspect_err(|err| {
+            error!("Unable to delete exhausted mfa ticket - {}", err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 23)))
+}
+
+async fn create_one_time_token(
+    prefix: &str,
+    seconds: u64,
+    user_id: String,
+    mut redis: MultiplexedConnection,
+) -> Result<String, FensterError> {
+    let token = generate_token();
+
+    redis.set_ex::<_, _, ()>(format!("{prefix}{}", hash_token(token.as_str())), user_id.clone(), seconds).await
+        .inspect_err(|err| {
+            error!("Unable to set one time token ({prefix}) for user_id ({}) - {}", user_id, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 13)))?;
+
+    Ok(token)
+}
+
+async fn consume_one_time_token(
+    prefix: &str,
+    token: String,
+    mut redis: MultiplexedConnection,
+) -> Result<Option<String>, FensterError> {
+    let user_id: Option<String> = redis.get_del(format!("{prefix}{}", hash_token(token.as_str()))).await
+        .inspect_err(|err| {
+            error!("Unable to get user_id from one time token ({prefix}) - {}", err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 14)))?;
+
+    Ok(user_id)
+}
+
+fn issue_auth_token(jwt: Option<&JwtKeys>, user: &User, session_id: &str) -> Result<AccessToken, FensterError> {
+    match jwt {
+        Some(keys) => jwt_entity::encode(keys, user, session_id),
+        None => token_with_expiration(AUTH_TOKEN_DAYS),
+    }
+}
+
 fn token_with_expiration(days: u64) -> Result<AccessToken, FensterError> {
     let sys_time = get_sys_time()?;
 
@@ -147,7 +480,7 @@ fn token_with_expiration(days: u64) -> Result<AccessToken, FensterError> {
     })
 }
 
-fn get_sys_time() -> Result<Duration, FensterError> {
+pub fn get_sys_time() -> Result<Duration, FensterError> {
     SystemTime::now().duration_since(UNIX_EPOCH)
         .inspect_err(|err| {
             error!("Error while loading duration since unix_epoch. - {}", err)
@@ -155,10 +488,20 @@ fn get_sys_time() -> Result<Duration, FensterError> {
         .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 2)))
 }
 
+fn expire_at(expiration_period: u128) -> i64 {
+    i64::try_from(expiration_period).unwrap_or(i64::MAX)
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex(Sha256::digest(token.as_bytes()).as_slice())
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
 
 pub fn generate_token() -> String {
-    let mut rng = rand::thread_rng();
-    iter::repeat_with(
-        || CHARS.as_bytes()[rng.gen_range(0..CHARS.len())] as char
-    ).take(16).collect()
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    hex(&bytes)
 }
\ No newline at end of file
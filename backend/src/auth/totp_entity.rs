@@ -0,0 +1,197 @@
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::Serialize;
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+use tracing::error;
+
+use crate::auth::token_entity::{get_sys_time, hash_token, hex};
+use crate::backend_config::School;
+use crate::fenster_error::{error, FensterError, OTHER_INTERNAL_ERROR, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Conflict, Internal};
+use crate::user::user_entity::User;
+
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_CODE_BYTES: usize = 6;
+const TOTP_STEP_SECONDS: u64 = 30;
+const TOTP_SKEW: u64 = 1;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+pub async fn enroll(user: &User, school: &School, pool: &PgPool) -> Result<TotpEnrollment, FensterError> {
+    if user.totp_enabled {
+        return Err(Conflict(format!("User with given id ({}) already has two-factor authentication enabled.", user.id)));
+    }
+
+    let secret = Secret::generate_secret().to_encoded().to_string();
+    let totp = totp(secret.as_str(), user, school)?;
+
+    sqlx::query!(
+        "UPDATE fenster.public.users SET user_totp_secret=$2, user_totp_enabled=FALSE WHERE user_id=$1",
+        &user.id, &secret)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while storing totp secret of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 18)))?;
+
+    Ok(TotpEnrollment {
+        secret,
+        otpauth_uri: totp.get_url(),
+    })
+}
+
+pub async fn confirm(user: &User, school: &School, code: &str, pool: &PgPool) -> Result<bool, FensterError> {
+    if !verify(user, school, code, pool).await? {
+        return Ok(false);
+    }
+
+    sqlx::query!("UPDATE fenster.public.users SET user_totp_enabled=TRUE WHERE user_id=$1", &user.id)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while enabling totp of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 19)))?;
+
+    Ok(true)
+}
+
+pub async fn disable(user: &User, pool: &PgPool) -> Result<(), FensterError> {
+    sqlx::query!(
+        "UPDATE fenster.public.users SET user_totp_secret=NULL, user_totp_enabled=FALSE WHERE user_id=$1",
+        &user.id)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while disabling totp of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 20)))?;
+
+    sqlx::query!("DELETE FROM fenster.public.user_recovery_codes WHERE user_id=$1", &user.id)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while deleting recovery codes of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 21)))?;
+
+    Ok(())
+}
+
+pub async fn verify(user: &User, school: &School, code: &str, pool: &PgPool) -> Result<bool, FensterError> {
+    let result = sqlx::query!(
+        "SELECT user_totp_secret FROM fenster.public.users WHERE user_id=$1", &user.id)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching totp secret of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 22)))?;
+
+    let Some(secret) = result.user_totp_secret else {
+        return Ok(false);
+    };
+
+    let totp = totp(secret.as_str(), user, school)?;
+    let current = get_sys_time()?.as_secs() / TOTP_STEP_SECONDS;
+
+    let Some(step) = (current.saturating_sub(TOTP_SKEW)..=current + TOTP_SKEW)
+        .find(|step| totp.check(code, step * TOTP_STEP_SECONDS)) else {
+        return Ok(false);
+    };
+
+    let result = sqlx::query!(
+        "UPDATE fenster.public.users SET user_totp_step=$2
+        WHERE user_id=$1 AND (user_totp_step IS NULL OR user_totp_step < $2)",
+        &user.id, step as i64)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while storing totp step of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 75)))?;
+
+    Ok(result.rows_affected() != 0)
+}
+
+pub async fn create_recovery_codes(user: &User, pool: &PgPool) -> Result<RecoveryCodes, FensterError> {
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODES).map(|_| {
+        let mut bytes = [0u8; RECOVERY_CODE_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        hex(&bytes)
+    }).collect();
+
+    let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_token(code)).collect();
+
+    let mut transaction = pool.begin().await
+        .inspect_err(|err| {
+            error!("Error while starting transaction for recovery codes of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 23)))?;
+
+    sqlx::query!("DELETE FROM fenster.public.user_recovery_codes WHERE user_id=$1", &user.id)
+        .execute(&mut *transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while deleting recovery codes of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 80)))?;
+
+    sqlx::query!(
+        "INSERT INTO fenster.public.user_recovery_codes(user_id, code_hash)
+        SELECT $1, UNNEST($2::TEXT[])",
+        &user.id, &hashes)
+        .execute(&mut *transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while storing recovery codes of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 24)))?;
+
+    transaction.commit().await
+        .inspect_err(|err| {
+            error!("Error while committing recovery codes of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 81)))?;
+
+    Ok(RecoveryCodes { recovery_codes })
+}
+
+pub async fn consume_recovery_code(user: &User, code: &str, pool: &PgPool) -> Result<bool, FensterError> {
+    let result = sqlx::query!(
+        "DELETE FROM fenster.public.user_recovery_codes WHERE user_id=$1 AND code_hash=$2",
+        &user.id, hash_token(code))
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while consuming recovery code of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 25)))?;
+
+    Ok(result.rows_affected() != 0)
+}
+
+fn totp(secret: &str, user: &User, school: &School) -> Result<TOTP, FensterError> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes()
+        .inspect_err(|err| {
+            error!("Error while decoding totp secret of user with id ({}). - {:?}", user.id, err)
+        })
+        .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 6)))?;
+
+    TOTP::new(Algorithm::SHA1, 6, 0, TOTP_STEP_SECONDS, bytes, Some(school.name.replace(':', "")), user.id.replace(':', ""))
+        .inspect_err(|err| {
+            error!("Error while creating totp of user with id ({}). - {}", user.id, err)
+        })
+        .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 7)))
+}
@@ -1,11 +1,25 @@
+use std::net::IpAddr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::user::user_role::Role;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct BackendConfig {
+    pub public_url: String,
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
     pub host: Host,
     pub postgres: Postgres,
     pub redis: Host,
     pub school: School,
+    pub mail: Mail,
+    #[serde(default)]
+    pub auth: Auth,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub scheduler: Scheduler,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -27,4 +41,170 @@ pub struct School {
     pub name: String,
     pub mail_pattern: String,
     pub example_mail: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Mail {
+    pub from: String,
+    #[serde(flatten)]
+    pub transport: MailTransport,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "transport", rename_all = "lowercase")]
+pub enum MailTransport {
+    Smtp {
+        address: String,
+        port: u16,
+        user: String,
+        password: String,
+        security: SmtpSecurity,
+    },
+    File {
+        directory: String,
+    },
+    Memory,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    Tls,
+    StartTls,
+    None,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimit {
+    pub auth: Bucket,
+    pub reads: Bucket,
+    pub writes: Bucket,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bucket {
+    pub capacity: u64,
+    pub refill_per_second: f64,
+}
+
+impl RateLimit {
+    pub fn check(&self) -> Result<(), String> {
+        for (name, bucket) in [("auth", &self.auth), ("reads", &self.reads), ("writes", &self.writes)] {
+            if bucket.capacity == 0 {
+                return Err(format!("rate_limit.{} capacity must be greater than 0.", name));
+            }
+
+            if !bucket.refill_per_second.is_finite() || bucket.refill_per_second <= 0.0 {
+                return Err(format!("rate_limit.{} refill_per_second must be greater than 0.", name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            auth: Bucket { capacity: 10, refill_per_second: 0.2 },
+            reads: Bucket { capacity: 120, refill_per_second: 20.0 },
+            writes: Bucket { capacity: 30, refill_per_second: 1.0 },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Scheduler {
+    pub enabled: bool,
+    pub publish_interval_seconds: u64,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler {
+            enabled: true,
+            publish_interval_seconds: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Auth {
+    pub mode: AuthMode,
+    pub jwt: Option<Jwt>,
+    #[serde(default)]
+    pub lockout: Lockout,
+    #[serde(default)]
+    pub password: PasswordPolicy,
+    #[serde(default)]
+    pub totp: TotpPolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TotpPolicy {
+    pub required_roles: Vec<Role>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 10,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Lockout {
+    pub max_attempts: u64,
+    pub window_seconds: u64,
+    pub base_delay_seconds: u64,
+    pub max_delay_seconds: u64,
+}
+
+impl Default for Lockout {
+    fn default() -> Self {
+        Lockout {
+            max_attempts: 5,
+            window_seconds: 60 * 60,
+            base_delay_seconds: 30,
+            max_delay_seconds: 60 * 60,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMode {
+    #[default]
+    Redis,
+    Jwt,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Jwt {
+    pub algorithm: JwtAlgorithm,
+    pub secret: Option<String>,
+    pub private_key: Option<String>,
+    pub public_key: Option<String>,
+    pub expiration_minutes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum JwtAlgorithm {
+    Hs256,
+    EdDsa,
 }
\ No newline at end of file
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use axum::async_trait;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::fenster_error::{error, FensterError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Conflict, Internal, NotFound};
+use crate::taxonomy::{Taxonomy, Term};
+
+pub type Category = Term;
+
+pub struct Categories;
+
+#[async_trait]
+impl Taxonomy for Categories {
+    const NAME: &'static str = "Category";
+
+    async fn store(category: &Category, pool: &PgPool) -> Result<(), FensterError> {
+        sqlx::query!(
+            "INSERT INTO fenster.public.categories (category_slug, category_name) VALUES ($1, $2)",
+            &category.slug, &category.name)
+            .execute(pool)
+            .await
+            .map_err(|err| match err {
+                sqlx::Error::Database(err) if err.is_unique_violation() =>
+                    Conflict(format!("Category with given slug ({}) already exists.", category.slug)),
+                err => {
+                    error!("Error while saving category with category_slug ({}). - {}", category.slug, err);
+                    Internal(error(POSTGRES_ERROR, 37))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    async fn exists(slug: &str, pool: &PgPool) -> Result<bool, FensterError> {
+        let result = sqlx::query!(
+            "SELECT EXISTS (SELECT 1 FROM fenster.public.categories WHERE category_slug=$1) AS \"exists!\"", slug)
+            .fetch_one(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while finding category with category_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 38)))?;
+
+        Ok(result.exists)
+    }
+
+    async fn fetch(slug: &str, pool: &PgPool) -> Result<Category, FensterError> {
+        let result = sqlx::query!(
+            "SELECT category_slug, category_name FROM fenster.public.categories WHERE category_slug=$1", slug)
+            .fetch_optional(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while fetching category with category_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 39)))?
+            .ok_or(NotFound(format!("Category with given slug ({}) does not exist.", slug)))?;
+
+        Ok(Category {
+            slug: result.category_slug,
+            name: result.category_name,
+        })
+    }
+
+    async fn list(pool: &PgPool) -> Result<Vec<Category>, FensterError> {
+        let rows = sqlx::query!(
+            "SELECT category_slug, category_name FROM fenster.public.categories ORDER BY category_name")
+            .fetch_all(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while listing categories. - {}", err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 40)))?;
+
+        Ok(rows.into_iter().map(|row| Category {
+            slug: row.category_slug,
+            name: row.category_name,
+        }).collect())
+    }
+
+    async fn rename(slug: &str, name: &str, pool: &PgPool) -> Result<(), FensterError> {
+        sqlx::query!(
+            "UPDATE fenster.public.categories SET category_name=$2 WHERE category_slug=$1", slug, name)
+            .execute(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while renaming category with category_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 41)))?;
+
+        Ok(())
+    }
+
+    async fn delete(slug: &str, pool: &PgPool) -> Result<(), FensterError> {
+        sqlx::query!("DELETE FROM fenster.public.categories WHERE category_slug=$1", slug)
+            .execute(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while deleting category with category_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 42)))?;
+
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1 @@
+pub(crate) mod category_entity;
\ No newline at end of file
@@ -0,0 +1,138 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::FORWARDED;
+use axum::http::HeaderMap;
+use axum::http::request::Parts;
+use tracing::error;
+
+use crate::AppInject;
+use crate::fenster_error::{error, FensterError, OTHER_INTERNAL_ERROR};
+use crate::fenster_error::FensterError::Internal;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl FromRequestParts<AppInject> for ClientIp {
+    type Rejection = FensterError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(address) = ConnectInfo::<SocketAddr>::from_request_parts(parts, state).await
+            .inspect_err(|err| {
+                error!("Error while reading the peer address. - {}", err)
+            })
+            .map_err(|_| Internal(error(OTHER_INTERNAL_ERROR, 12)))?;
+
+        Ok(ClientIp(resolve(address.ip(), &parts.headers, &state.backend_config.trusted_proxies)))
+    }
+}
+
+fn resolve(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let chain = if headers.contains_key(FORWARDED) {
+        forwarded(headers)
+    } else {
+        x_forwarded_for(headers)
+    };
+
+    for hop in chain.into_iter().rev() {
+        match hop {
+            Some(ip) if trusted_proxies.contains(&ip) => continue,
+            Some(ip) => return ip,
+            None => break,
+        }
+    }
+
+    peer
+}
+
+fn forwarded(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    values(headers, FORWARDED.as_str())
+        .filter_map(|element| element.split(';')
+            .map(str::trim)
+            .find(|pair| pair.len() > 4 && pair[..4].eq_ignore_ascii_case("for="))
+            .map(|pair| node(&pair[4..])))
+        .collect()
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    values(headers, X_FORWARDED_FOR).map(node).collect()
+}
+
+fn values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers.get_all(name).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+}
+
+fn node(node: &str) -> Option<IpAddr> {
+    let node = node.trim_matches('"');
+
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+
+    node.parse().ok()
+        .or_else(|| node.rsplit_once(':')?.0.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn untrusted_peers_are_the_client() {
+        let headers = headers(X_FORWARDED_FOR, "203.0.113.7");
+
+        assert_eq!(resolve(ip("198.51.100.1"), &headers, &[ip("10.0.0.1")]), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn trusted_proxies_forward_the_client() {
+        let headers = headers(X_FORWARDED_FOR, "203.0.113.7, 10.0.0.2");
+
+        assert_eq!(resolve(ip("10.0.0.1"), &headers, &[ip("10.0.0.1"), ip("10.0.0.2")]), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn spoofed_hops_left_of_the_client_are_ignored() {
+        let headers = headers(X_FORWARDED_FOR, "1.1.1.1, 203.0.113.7");
+
+        assert_eq!(resolve(ip("10.0.0.1"), &headers, &[ip("10.0.0.1")]), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn forwarded_header_is_preferred() {
+        let mut headers = headers("forwarded", r#"for=192.0.2.60;proto=http, For="[2001:db8::1]:4711""#);
+        headers.insert(X_FORWARDED_FOR, HeaderValue::from_static("203.0.113.7"));
+
+        assert_eq!(resolve(ip("10.0.0.1"), &headers, &[ip("10.0.0.1")]), ip("2001:db8::1"));
+    }
+
+    #[test]
+    fn unparsable_hops_fall_back_to_the_peer() {
+        let headers = headers("forwarded", "for=unknown");
+
+        assert_eq!(resolve(ip("10.0.0.1"), &headers, &[ip("10.0.0.1")]), ip("10.0.0.1"));
+    }
+}
\ No newline at end of file
@@ -1,34 +1,180 @@
-use axum::http::StatusCode;
+use axum::http::header::{CONTENT_TYPE, RETRY_AFTER};
+use axum::http::{HeaderValue, StatusCode};
+use axum::Json;
 use axum::response::{IntoResponse, Response};
+use serde::Serialize;
 
 use crate::fenster_error::FensterError::*;
+use crate::request_id;
 
 #[derive(Debug)]
 pub enum FensterError {
     NotFound(String),
-    Internal(u16),
+    Internal(u32),
     Conflict(String),
     Unauthorized(String),
+    Forbidden(String),
+    TooManyRequests(String, u64),
+    Validation(Vec<FieldError>),
 }
 
-pub const OTHER_INTERNAL_ERROR: u16 = 111;
-pub const POSTGRES_ERROR: u16 = 222;
-pub const REDIS_ERROR: u16 = 444;
+/// Source of an internal error. The numeric internal codes are built as `source * 1000 + index`,
+/// e.g. `222035` is the postgres failure site 35. Clients see the stable `code` and, to report it,
+/// the numeric `internal_code`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InternalError {
+    /// Anything that is neither a database, cache nor mail failure (config, hashing, token generation).
+    Other = 111,
+    /// A query against postgres failed.
+    Postgres = 222,
+    /// A command against redis failed.
+    Redis = 444,
+    /// Building or sending a mail failed.
+    Mail = 555,
+}
+
+pub const OTHER_INTERNAL_ERROR: u16 = InternalError::Other as u16;
+pub const POSTGRES_ERROR: u16 = InternalError::Postgres as u16;
+pub const REDIS_ERROR: u16 = InternalError::Redis as u16;
+pub const MAIL_ERROR: u16 = InternalError::Mail as u16;
+
+/// Highest index a failure site of one source may use.
+pub const MAX_ERROR_INDEX: u16 = 999;
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+pub fn error(error: u16, i: u16) -> u32 {
+    debug_assert!(i <= MAX_ERROR_INDEX, "Error index ({}) exceeds {}.", i, MAX_ERROR_INDEX);
+    error as u32 * (MAX_ERROR_INDEX as u32 + 1) + i as u32
+}
+
+impl InternalError {
+    pub fn from_code(code: u32) -> Option<InternalError> {
+        match code / (MAX_ERROR_INDEX as u32 + 1) {
+            111 => Some(InternalError::Other),
+            222 => Some(InternalError::Postgres),
+            444 => Some(InternalError::Redis),
+            555 => Some(InternalError::Mail),
+            _ => None,
+        }
+    }
 
-pub fn error(error: u16, i: u16) -> u16 {
-    error * 10 + i
+    pub fn code(&self) -> &'static str {
+        match self {
+            InternalError::Other => "internal_error",
+            InternalError::Postgres => "database_error",
+            InternalError::Redis => "cache_error",
+            InternalError::Mail => "mail_error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub code: &'static str,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub internal_code: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl IntoResponse for FensterError {
     fn into_response(self) -> Response {
-        match self {
-            NotFound(message) => (StatusCode::NOT_FOUND, message),
-            Conflict(message) => (StatusCode::CONFLICT, message),
-            Unauthorized(message) => (StatusCode::UNAUTHORIZED, message),
-            Internal(code) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Internal server error. Code: {} | Please contact the responsible.", code)
-            ),
-        }.into_response()
+        let mut retry_after = None;
+        let mut internal_code = None;
+        let mut errors = vec![];
+
+        let (status, code, detail) = match self {
+            NotFound(message) => (StatusCode::NOT_FOUND, "not_found", message),
+            Conflict(message) => (StatusCode::CONFLICT, "conflict", message),
+            Unauthorized(message) => (StatusCode::UNAUTHORIZED, "unauthorized", message),
+            Forbidden(message) => (StatusCode::FORBIDDEN, "forbidden", message),
+            TooManyRequests(message, seconds) => {
+                retry_after = Some(seconds);
+                (StatusCode::TOO_MANY_REQUESTS, "too_many_requests", message)
+            }
+            Validation(fields) => {
+                errors = fields;
+                (StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", "One or more fields are invalid.".to_string())
+            }
+            Internal(internal) => {
+                internal_code = Some(internal);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    InternalError::from_code(internal).map_or("internal_error", |internal| internal.code()),
+                    format!("Internal server error. Code: {} | Please contact the responsible.", internal),
+                )
+            }
+        };
+
+        let problem = Problem {
+            problem_type: format!("urn:fenster:problem:{}", code),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            code,
+            detail,
+            internal_code,
+            errors,
+            request_id: request_id::current(),
+        };
+
+        let mut response = (status, Json(problem)).into_response();
+        response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+
+        if let Some(seconds) = retry_after {
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+
+        response
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCES: [InternalError; 4] = [InternalError::Other, InternalError::Postgres, InternalError::Redis, InternalError::Mail];
+
+    #[test]
+    fn codes_round_trip_for_every_index() {
+        for source in SOURCES {
+            for index in 0..=MAX_ERROR_INDEX {
+                assert_eq!(InternalError::from_code(error(source as u16, index)), Some(source));
+            }
+        }
+    }
+
+    #[test]
+    fn codes_of_highest_indices_in_use() {
+        assert_eq!(InternalError::from_code(error(OTHER_INTERNAL_ERROR, 11)), Some(InternalError::Other));
+        assert_eq!(InternalError::from_code(error(POSTGRES_ERROR, 89)), Some(InternalError::Postgres));
+        assert_eq!(InternalError::from_code(error(REDIS_ERROR, 27)), Some(InternalError::Redis));
+        assert_eq!(InternalError::from_code(error(MAIL_ERROR, 6)), Some(InternalError::Mail));
+    }
+
+    #[test]
+    fn codes_are_unique_per_site() {
+        assert_eq!(error(POSTGRES_ERROR, 35), 222035);
+        assert_ne!(error(POSTGRES_ERROR, 35), error(POSTGRES_ERROR, 3));
+    }
+
+    #[test]
+    fn unknown_codes_have_no_source() {
+        assert_eq!(InternalError::from_code(0), None);
+        assert_eq!(InternalError::from_code(333000), None);
     }
 }
\ No newline at end of file
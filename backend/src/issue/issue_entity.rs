@@ -0,0 +1,432 @@
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::article::article_entity;
+use crate::article::article_entity::Article;
+use crate::article::article_state::ArticleState;
+use crate::backend_config::BackendConfig;
+use crate::fenster_error::{error, FensterError, FieldError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Conflict, Internal, NotFound, Validation};
+use crate::user::user_entity::User;
+use crate::validation::{double_option, page_offset, Validate, Validator};
+
+const TITLE_MAX_LENGTH: usize = 200;
+const COVER_IMAGE_MAX_LENGTH: usize = 2048;
+const ARTICLES_PER_ISSUE: usize = 100;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Issue {
+    pub(crate) number: i32,
+    pub(crate) title: String,
+    pub(crate) cover_image: Option<String>,
+    pub(crate) release_date: NaiveDate,
+    pub(crate) published: bool,
+    pub(crate) publish_at: Option<NaiveDateTime>,
+    pub(crate) articles: Vec<Article>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IssueSummary {
+    pub(crate) number: i32,
+    pub(crate) title: String,
+    pub(crate) cover_image: Option<String>,
+    pub(crate) release_date: NaiveDate,
+    pub(crate) published: bool,
+    pub(crate) publish_at: Option<NaiveDateTime>,
+    pub(crate) article_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IssuePage {
+    pub(crate) issues: Vec<IssueSummary>,
+    pub(crate) page: i64,
+    pub(crate) per_page: i64,
+    pub(crate) total: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ListIssues {
+    pub(crate) page: Option<i64>,
+    pub(crate) per_page: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateIssue {
+    pub(crate) number: i32,
+    pub(crate) title: String,
+    pub(crate) cover_image: Option<String>,
+    pub(crate) release_date: NaiveDate,
+    pub(crate) publish_at: Option<NaiveDateTime>,
+    pub(crate) articles: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EditIssue {
+    pub(crate) title: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub(crate) cover_image: Option<Option<String>>,
+    pub(crate) release_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub(crate) publish_at: Option<Option<NaiveDateTime>>,
+    pub(crate) articles: Option<Vec<String>>,
+}
+
+impl Validate for CreateIssue {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("number", "").require(self.number > 0, "Must be a positive number.");
+        validator.field("title", &self.title).length(1, TITLE_MAX_LENGTH);
+        validate_contents(self.cover_image.as_ref(), Some(&self.articles), validator);
+    }
+}
+
+impl Validate for EditIssue {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        if let Some(title) = &self.title {
+            validator.field("title", title).length(1, TITLE_MAX_LENGTH);
+        }
+
+        validate_contents(self.cover_image.as_ref().and_then(Option::as_ref), self.articles.as_ref(), validator);
+    }
+}
+
+fn validate_contents(cover_image: Option<&String>, articles: Option<&Vec<String>>, validator: &mut Validator) {
+    if let Some(cover_image) = cover_image {
+        validator.field("cover_image", cover_image).length(1, COVER_IMAGE_MAX_LENGTH)
+            .require(
+                cover_image.starts_with("https://") || cover_image.starts_with("http://") || cover_image.starts_with('/'),
+                "Must be an absolute http(s) url or a path.",
+            );
+    }
+
+    if let Some(articles) = articles {
+        validator.field("articles", "")
+            .require(articles.len() <= ARTICLES_PER_ISSUE, format!("Must contain at most {} articles.", ARTICLES_PER_ISSUE).as_str());
+
+        for article in articles {
+            validator.field("articles", article).slug();
+        }
+    }
+}
+
+impl Issue {
+    pub async fn store(&self, slugs: &[String], pool: &PgPool) -> Result<(), FensterError> {
+        let mut transaction = pool.begin().await
+            .inspect_err(|err| {
+                error!("Error while starting transaction for issue with issue_number ({}). - {}", self.number, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 53)))?;
+
+        let result = sqlx::query!(
+            "INSERT INTO fenster.public.issues
+            (issue_number, issue_title, issue_cover_image, issue_release_date, issue_published, issue_publish_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (issue_number) DO NOTHING",
+            self.number, &self.title, self.cover_image.as_ref(), self.release_date, self.published, self.publish_at)
+            .execute(&mut *transaction)
+            .await
+            .inspect_err(|err| {
+                error!("Error while saving issue with issue_number ({}). - {}", self.number, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 54)))?;
+
+        if result.rows_affected() == 0 {
+            return Err(Conflict(format!("Issue with given number ({}) already exists.", self.number)));
+        }
+
+        assign_articles(self.number, slugs, &mut transaction).await?;
+
+        transaction.commit().await
+            .inspect_err(|err| {
+                error!("Error while committing issue with issue_number ({}). - {}", self.number, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 85)))?;
+
+        Ok(())
+    }
+
+    pub async fn update(&self, slugs: &[String], pool: &PgPool) -> Result<(), FensterError> {
+        let mut transaction = pool.begin().await
+            .inspect_err(|err| {
+                error!("Error while starting transaction for updating issue with issue_number ({}). - {}", self.number, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 87)))?;
+
+        sqlx::query!(
+            "UPDATE fenster.public.issues
+            SET issue_title=$2, issue_cover_image=$3, issue_release_date=$4, issue_publish_at=$5
+            WHERE issue_number=$1",
+            self.number, &self.title, self.cover_image.as_ref(), self.release_date, self.publish_at)
+            .execute(&mut *transaction)
+            .await
+            .inspect_err(|err| {
+                error!("Error while updating issue with issue_number ({}). - {}", self.number, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 88)))?;
+
+        assign_articles(self.number, slugs, &mut transaction).await?;
+
+        transaction.commit().await
+            .inspect_err(|err| {
+                error!("Error while committing update of issue with issue_number ({}). - {}", self.number, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 89)))?;
+
+        Ok(())
+    }
+}
+
+async fn assign_articles(number: i32, slugs: &[String], transaction: &mut Transaction<'_, Postgres>) -> Result<(), FensterError> {
+    sqlx::query!("DELETE FROM fenster.public.issue_articles WHERE issue_number=$1", number)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while clearing articles of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 55)))?;
+
+    sqlx::query!(
+        "INSERT INTO fenster.public.issue_articles (issue_number, article_id, position)
+        SELECT $1, a.article_id, s.position
+        FROM UNNEST($2::TEXT[]) WITH ORDINALITY AS s(slug, position)
+        JOIN fenster.public.articles a ON a.article_slug = s.slug",
+        number, slugs)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while saving articles of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 56)))?;
+
+    Ok(())
+}
+
+pub async fn exists(number: i32, pool: &PgPool) -> Result<bool, FensterError> {
+    let result = sqlx::query!(
+        "SELECT EXISTS (SELECT 1 FROM fenster.public.issues WHERE issue_number=$1) AS \"exists!\"", number)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while finding issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 57)))?;
+
+    Ok(result.exists)
+}
+
+pub async fn fetch(number: i32, include_unpublished: bool, pool: &PgPool) -> Result<Issue, FensterError> {
+    let issue = sqlx::query!(
+        "SELECT issue_number, issue_title, issue_cover_image, issue_release_date, issue_published, issue_publish_at
+        FROM fenster.public.issues WHERE issue_number=$1 AND (issue_published OR $2)",
+        number, include_unpublished)
+        .fetch_optional(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 58)))?
+        .ok_or(NotFound(format!("Issue with given number ({}) does not exist.", number)))?;
+
+    let articles = article_entity::list_for_issue(number, include_unpublished, pool).await?;
+
+    Ok(Issue {
+        number: issue.issue_number,
+        title: issue.issue_title,
+        cover_image: issue.issue_cover_image,
+        release_date: issue.issue_release_date,
+        published: issue.issue_published,
+        publish_at: issue.issue_publish_at,
+        articles,
+    })
+}
+
+pub async fn list(list: &ListIssues, include_unpublished: bool, pool: &PgPool) -> Result<IssuePage, FensterError> {
+    let page = list.page.unwrap_or(0).max(0);
+    let per_page = list.per_page.unwrap_or(20).clamp(1, 100);
+    let offset = page_offset(page, per_page)?;
+
+    let total = sqlx::query!(
+        r#"SELECT COUNT(*) AS "total!" FROM fenster.public.issues WHERE issue_published OR $1"#, include_unpublished)
+        .fetch_one(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while counting issues. - {}", err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 78)))?
+        .total;
+
+    let rows = sqlx::query!(
+        r#"SELECT i.issue_number, i.issue_title, i.issue_cover_image, i.issue_release_date, i.issue_published, i.issue_publish_at,
+        (SELECT COUNT(*) FROM fenster.public.issue_articles ia WHERE ia.issue_number = i.issue_number) AS "article_count!"
+        FROM fenster.public.issues i
+        WHERE i.issue_published OR $1
+        ORDER BY i.issue_number DESC
+        LIMIT $2 OFFSET $3"#,
+        include_unpublished, per_page, offset)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while listing issues. - {}", err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 60)))?;
+
+    let issues = rows.into_iter().map(|row| IssueSummary {
+        number: row.issue_number,
+        title: row.issue_title,
+        cover_image: row.issue_cover_image,
+        release_date: row.issue_release_date,
+        published: row.issue_published,
+        publish_at: row.issue_publish_at,
+        article_count: row.article_count,
+    }).collect();
+
+    Ok(IssuePage {
+        issues,
+        page,
+        per_page,
+        total,
+    })
+}
+
+pub async fn article_slugs(number: i32, pool: &PgPool) -> Result<Vec<String>, FensterError> {
+    let rows = sqlx::query!(
+        "SELECT a.article_slug FROM fenster.public.issue_articles ia
+        JOIN fenster.public.articles a ON a.article_id = ia.article_id
+        WHERE ia.issue_number=$1 ORDER BY ia.position",
+        number)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching article slugs of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 61)))?;
+
+    Ok(rows.into_iter().map(|row| row.article_slug).collect())
+}
+
+pub async fn require_assignable(slugs: &[String], number: i32, pool: &PgPool) -> Result<(), FensterError> {
+    let rows = sqlx::query!(
+        r#"SELECT s.slug AS "slug!", a.article_id AS "article_id?", ia.issue_number AS "issue_number?"
+        FROM UNNEST($1::TEXT[]) AS s(slug)
+        LEFT JOIN fenster.public.articles a ON a.article_slug = s.slug
+        LEFT JOIN fenster.public.issue_articles ia ON ia.article_id = a.article_id"#,
+        slugs)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while checking articles for issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 62)))?;
+
+    let missing: Vec<FieldError> = rows.iter()
+        .filter(|row| row.article_id.is_none())
+        .map(|row| FieldError {
+            field: "articles".to_string(),
+            message: format!("Article with given slug ({}) does not exist.", row.slug),
+        })
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(Validation(missing));
+    }
+
+    if let Some(row) = rows.iter().find(|row| row.issue_number.is_some_and(|issue| issue != number)) {
+        return Err(Conflict(format!(
+            "Article with given slug ({}) already belongs to issue ({}).", row.slug, row.issue_number.unwrap_or_default())));
+    }
+
+    Ok(())
+}
+
+pub async fn publish(number: i32, publisher: Option<&User>, pool: &PgPool) -> Result<(), FensterError> {
+    let mut transaction = pool.begin().await
+        .inspect_err(|err| {
+            error!("Error while starting transaction for publishing issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 63)))?;
+
+    let unapproved: Vec<String> = sqlx::query!(
+        r#"SELECT a.article_slug, a.article_state AS "article_state: ArticleState"
+        FROM fenster.public.issue_articles ia
+        JOIN fenster.public.articles a ON a.article_id = ia.article_id
+        WHERE ia.issue_number=$1
+        ORDER BY ia.position
+        FOR UPDATE OF a"#,
+        number)
+        .fetch_all(&mut *transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while checking article states of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 74)))?
+        .into_iter()
+        .filter(|row| !matches!(row.article_state, ArticleState::Approved | ArticleState::Published))
+        .map(|row| row.article_slug)
+        .collect();
+
+    if !unapproved.is_empty() {
+        return Err(Conflict(format!(
+            "Issue with given number ({}) contains articles that are not approved ({}).", number, unapproved.join(", "))));
+    }
+
+    sqlx::query!(
+        "UPDATE fenster.public.issues SET issue_published=TRUE, issue_publish_at=NULL WHERE issue_number=$1", number)
+        .execute(&mut *transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while publishing issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 64)))?;
+
+    sqlx::query!(
+        "WITH published AS (UPDATE fenster.public.articles
+            SET article_state='published', article_publish_at=NULL, editing_date=NOW() AT TIME ZONE 'UTC'
+            WHERE article_state='approved'
+            AND article_id IN (SELECT article_id FROM fenster.public.issue_articles WHERE issue_number=$1)
+            RETURNING article_id)
+        INSERT INTO fenster.public.article_state_changes (article_id, change_from, change_to, change_user, creation_date)
+        SELECT article_id, 'approved', 'published', $2, NOW() AT TIME ZONE 'UTC' FROM published",
+        number, publisher.map(|user| user.id.clone()))
+        .execute(&mut *transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while publishing articles of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 65)))?;
+
+    transaction.commit().await
+        .inspect_err(|err| {
+            error!("Error while committing publishing of issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 86)))?;
+
+    Ok(())
+}
+
+pub async fn due(pool: &PgPool) -> Result<Vec<i32>, FensterError> {
+    let rows = sqlx::query!(
+        "SELECT issue_number FROM fenster.public.issues
+        WHERE NOT issue_published AND issue_publish_at <= NOW() AT TIME ZONE 'UTC'")
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching due issues. - {}", err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 68)))?;
+
+    Ok(rows.into_iter().map(|row| row.issue_number).collect())
+}
+
+pub async fn delete(number: i32, pool: &PgPool) -> Result<(), FensterError> {
+    sqlx::query!("DELETE FROM fenster.public.issues WHERE issue_number=$1", number)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while deleting issue with issue_number ({}). - {}", number, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 66)))?;
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use axum::{Json, Router};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{get, put};
+
+use crate::AppInject;
+use crate::auth::auth_extractor::{AuthenticatedUser, MaybeAuthenticatedUser};
+use crate::fenster_error::FensterError;
+use crate::fenster_error::FensterError::{Conflict, NotFound};
+use crate::issue::issue_entity;
+use crate::issue::issue_entity::{CreateIssue, EditIssue, Issue, IssuePage, ListIssues};
+use crate::user::user_role::Permission;
+use crate::validation::{PathParams, QueryParams, ValidJson};
+
+pub fn issue_router() -> Router<AppInject> {
+    Router::new()
+        .route("/", get(list_issues).post(create_issue))
+        .route("/:number", get(get_issue).patch(edit_issue).delete(delete_issue))
+        .route("/:number/publish", put(publish_issue))
+}
+
+pub async fn list_issues(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    MaybeAuthenticatedUser(user): MaybeAuthenticatedUser,
+    QueryParams(list): QueryParams<ListIssues>,
+) -> Result<Json<IssuePage>, FensterError> {
+    let include_unpublished = user.is_some_and(|user| user.has_permission(Permission::ManageArticles));
+
+    let page = issue_entity::list(&list, include_unpublished, &postgres_pool).await?;
+    Ok(Json(page))
+}
+
+pub async fn get_issue(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    MaybeAuthenticatedUser(user): MaybeAuthenticatedUser,
+    PathParams(number): PathParams<i32>,
+) -> Result<Json<Issue>, FensterError> {
+    let include_unpublished = user.is_some_and(|user| user.has_permission(Permission::ManageArticles));
+
+    let issue = issue_entity::fetch(number, include_unpublished, &postgres_pool).await?;
+    Ok(Json(issue))
+}
+
+pub async fn create_issue(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    ValidJson(create): ValidJson<CreateIssue>,
+) -> Result<Json<Issue>, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    if issue_entity::exists(create.number, &postgres_pool).await? {
+        return Err(Conflict(format!("Issue with given number ({}) already exists.", create.number)));
+    }
+
+    let slugs = deduplicate(create.articles);
+    issue_entity::require_assignable(&slugs, create.number, &postgres_pool).await?;
+
+    let issue = Issue {
+        number: create.number,
+        title: create.title,
+        cover_image: create.cover_image,
+        release_date: create.release_date,
+        published: false,
+        publish_at: create.publish_at,
+        articles: vec![],
+    };
+
+    issue.store(&slugs, &postgres_pool).await?;
+
+    let issue = issue_entity::fetch(issue.number, true, &postgres_pool).await?;
+    Ok(Json(issue))
+}
+
+pub async fn edit_issue(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(number): PathParams<i32>,
+    ValidJson(edit): ValidJson<EditIssue>,
+) -> Result<Json<Issue>, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    let mut issue = issue_entity::fetch(number, true, &postgres_pool).await?;
+
+    if let Some(title) = edit.title {
+        issue.title = title;
+    }
+
+    if let Some(cover_image) = edit.cover_image {
+        issue.cover_image = cover_image;
+    }
+
+    if let Some(release_date) = edit.release_date {
+        issue.release_date = release_date;
+    }
+
+    if let Some(publish_at) = edit.publish_at {
+        issue.publish_at = publish_at.filter(|_| !issue.published);
+    }
+
+    let slugs = match edit.articles {
+        Some(articles) => deduplicate(articles),
+        None => issue_entity::article_slugs(number, &postgres_pool).await?,
+    };
+
+    issue_entity::require_assignable(&slugs, number, &postgres_pool).await?;
+    issue.update(&slugs, &postgres_pool).await?;
+
+    let issue = issue_entity::fetch(number, true, &postgres_pool).await?;
+    Ok(Json(issue))
+}
+
+pub async fn delete_issue(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(number): PathParams<i32>,
+) -> Result<StatusCode, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    if !issue_entity::exists(number, &postgres_pool).await? {
+        return Err(NotFound(format!("Issue with given number ({}) does not exist.", number)));
+    }
+
+    issue_entity::delete(number, &postgres_pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn publish_issue(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(number): PathParams<i32>,
+) -> Result<Json<Issue>, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    if !issue_entity::exists(number, &postgres_pool).await? {
+        return Err(NotFound(format!("Issue with given number ({}) does not exist.", number)));
+    }
+
+    issue_entity::publish(number, Some(&user), &postgres_pool).await?;
+
+    let issue = issue_entity::fetch(number, true, &postgres_pool).await?;
+    Ok(Json(issue))
+}
+
+fn deduplicate(slugs: Vec<String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::with_capacity(slugs.len());
+
+    for slug in slugs {
+        if !unique.contains(&slug) {
+            unique.push(slug);
+        }
+    }
+
+    unique
+}
\ No newline at end of file
@@ -0,0 +1,2 @@
+pub(crate) mod issue_entity;
+pub(crate) mod issue_handler;
\ No newline at end of file
@@ -0,0 +1,46 @@
+use std::fs;
+
+use axum::async_trait;
+use lettre::{AsyncFileTransport, AsyncTransport, Tokio1Executor};
+use lettre::message::Mailbox;
+use tracing::error;
+
+use crate::fenster_error::{error, FensterError, MAIL_ERROR};
+use crate::fenster_error::FensterError::Internal;
+use crate::mail::{build_message, Mail, Mailer};
+
+pub struct FileMailer {
+    from: Mailbox,
+    transport: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileMailer {
+    pub fn new(from: Mailbox, directory: String) -> Result<Self, FensterError> {
+        fs::create_dir_all(directory.as_str())
+            .inspect_err(|err| {
+                error!("Error while creating mail directory ({}). - {}", directory, err)
+            })
+            .map_err(|_| Internal(error(MAIL_ERROR, 5)))?;
+
+        Ok(FileMailer {
+            from,
+            transport: AsyncFileTransport::new(directory),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), FensterError> {
+        let to = mail.to.clone();
+        let message = build_message(&self.from, mail)?;
+
+        self.transport.send(message).await
+            .inspect_err(|err| {
+                error!("Error while writing mail to ({}) into file. - {}", to, err)
+            })
+            .map_err(|_| Internal(error(MAIL_ERROR, 6)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::backend_config::School;
+use crate::html::escape;
+use crate::mail::Mail;
+use crate::user::user_entity::User;
+
+pub fn verification(school: &School, user: &User, link: &str) -> Mail {
+    render(school, user, "Verify your email address", &[
+        Line::Text("please confirm your email address by opening the following link:"),
+        Line::Link(link),
+        Line::Text("The link is valid for 24 hours."),
+    ])
+}
+
+pub fn password_reset(school: &School, user: &User, token: &str) -> Mail {
+    render(school, user, "Reset your password", &[
+        Line::Text("use the following token to reset your password:"),
+        Line::Code(token),
+        Line::Text("It is valid for one hour and can only be used once. \
+        If you did not request a reset, you can ignore this mail."),
+    ])
+}
+
+enum Line<'a> {
+    Text(&'a str),
+    Link(&'a str),
+    Code(&'a str),
+}
+
+fn render(school: &School, user: &User, subject: &str, lines: &[Line]) -> Mail {
+    let mut text = format!("Hello {},\n\n", user.name);
+    let mut html = format!("<!DOCTYPE html>\n<html>\n<body>\n<p>Hello {},</p>\n", escape(user.name.as_str()));
+
+    for line in lines {
+        let (Line::Text(value) | Line::Link(value) | Line::Code(value)) = line;
+        text.push_str(&format!("{}\n", value));
+
+        match line {
+            Line::Text(value) => html.push_str(&format!("<p>{}</p>\n", escape(value))),
+            Line::Link(value) => html.push_str(&format!("<p><a href=\"{0}\">{0}</a></p>\n", escape(value))),
+            Line::Code(value) => html.push_str(&format!("<p><code>{}</code></p>\n", escape(value))),
+        }
+    }
+
+    text.push_str(&format!("\nYour {} newspaper team", school.name));
+    html.push_str(&format!("<p>Your {} newspaper team</p>\n</body>\n</html>", escape(school.name.as_str())));
+
+    Mail {
+        to: user.email.clone(),
+        subject: format!("{} | {}", school.name, subject),
+        text,
+        html,
+    }
+}
@@ -0,0 +1,15 @@
+use axum::async_trait;
+use tracing::info;
+
+use crate::fenster_error::FensterError;
+use crate::mail::{Mail, Mailer};
+
+pub struct MemoryMailer;
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, mail: Mail) -> Result<(), FensterError> {
+        info!("Discarding mail ({}) to ({}).", mail.subject, mail.to);
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use lettre::Message;
+use lettre::message::{Mailbox, MultiPart};
+use tracing::error;
+
+use crate::backend_config::MailTransport;
+use crate::fenster_error::{error, FensterError, MAIL_ERROR};
+use crate::fenster_error::FensterError::Internal;
+use crate::mail::file_mailer::FileMailer;
+use crate::mail::memory_mailer::MemoryMailer;
+use crate::mail::smtp_mailer::SmtpMailer;
+
+pub(crate) mod mail_template;
+pub(crate) mod smtp_mailer;
+pub(crate) mod file_mailer;
+pub(crate) mod memory_mailer;
+
+#[derive(Clone, Debug)]
+pub struct Mail {
+    pub(crate) to: String,
+    pub(crate) subject: String,
+    pub(crate) text: String,
+    pub(crate) html: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), FensterError>;
+}
+
+pub fn create_mailer(mail: crate::backend_config::Mail) -> Result<Arc<dyn Mailer>, FensterError> {
+    let from = parse_mailbox(mail.from.as_str())?;
+
+    Ok(match mail.transport {
+        MailTransport::Smtp { address, port, user, password, security } =>
+            Arc::new(SmtpMailer::new(from, address, port, user, password, security)?),
+        MailTransport::File { directory } => Arc::new(FileMailer::new(from, directory)?),
+        MailTransport::Memory => Arc::new(MemoryMailer),
+    })
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, FensterError> {
+    address.parse::<Mailbox>()
+        .inspect_err(|err| {
+            error!("Error while parsing mail address ({}). - {}", address, err)
+        })
+        .map_err(|_| Internal(error(MAIL_ERROR, 1)))
+}
+
+fn build_message(from: &Mailbox, mail: Mail) -> Result<Message, FensterError> {
+    Message::builder()
+        .from(from.clone())
+        .to(parse_mailbox(mail.to.as_str())?)
+        .subject(mail.subject)
+        .multipart(MultiPart::alternative_plain_html(mail.text, mail.html))
+        .inspect_err(|err| {
+            error!("Error while building mail to ({}). - {}", mail.to, err)
+        })
+        .map_err(|_| Internal(error(MAIL_ERROR, 2)))
+}
@@ -0,0 +1,59 @@
+use axum::async_trait;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use tracing::error;
+
+use crate::backend_config::SmtpSecurity;
+use crate::fenster_error::{error, FensterError, MAIL_ERROR};
+use crate::fenster_error::FensterError::Internal;
+use crate::mail::{build_message, Mail, Mailer};
+
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        from: Mailbox,
+        address: String,
+        port: u16,
+        user: String,
+        password: String,
+        security: SmtpSecurity,
+    ) -> Result<Self, FensterError> {
+        let builder = match security {
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(address.as_str()),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(address.as_str()),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(address.as_str())),
+        }
+            .inspect_err(|err| {
+                error!("Error while creating smtp transport for ({}). - {}", address, err)
+            })
+            .map_err(|_| Internal(error(MAIL_ERROR, 3)))?;
+
+        let transport = builder
+            .port(port)
+            .credentials(Credentials::new(user, password))
+            .build();
+
+        Ok(SmtpMailer { from, transport })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), FensterError> {
+        let to = mail.to.clone();
+        let message = build_message(&self.from, mail)?;
+
+        self.transport.send(message).await
+            .inspect_err(|err| {
+                error!("Error while sending mail to ({}) over smtp. - {}", to, err)
+            })
+            .map_err(|_| Internal(error(MAIL_ERROR, 4)))?;
+
+        Ok(())
+    }
+}
@@ -1,30 +1,43 @@
 use std::fs;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::Arc;
 
 use anyhow::Result;
-use axum::error_handling::HandleErrorLayer;
-use axum::http::StatusCode;
-use axum::Router;
+use axum::{middleware, Router};
 use redis::aio::MultiplexedConnection;
 use sqlx::PgPool;
-use tower::buffer::BufferLayer;
-use tower::limit::RateLimitLayer;
-use tower::ServiceBuilder;
 use tracing::info;
-use crate::backend_config::BackendConfig;
+use crate::auth::jwt_entity::JwtKeys;
+use crate::backend_config::{AuthMode, BackendConfig};
+use crate::category::category_entity::Categories;
+use crate::mail::Mailer;
+use crate::tag::tag_entity::Tags;
 
 pub(crate) mod persistence;
 pub(crate) mod auth;
 pub(crate) mod user;
 pub(crate) mod article;
+pub(crate) mod category;
+pub(crate) mod tag;
+pub(crate) mod taxonomy;
+pub(crate) mod issue;
 pub(crate) mod fenster_error;
 pub(crate) mod backend_config;
+pub(crate) mod mail;
+pub(crate) mod html;
+pub(crate) mod rate_limit;
+pub(crate) mod request_id;
+pub(crate) mod client_ip;
+pub(crate) mod scheduler;
+pub(crate) mod validation;
 
 #[derive(Clone)]
 pub struct AppInject {
     pub postgres_pool: PgPool,
     pub redis_connection: MultiplexedConnection,
     pub backend_config: BackendConfig,
+    pub mailer: Arc<dyn Mailer>,
+    pub jwt: Option<Arc<JwtKeys>>,
 }
 
 #[tokio::main]
@@ -37,32 +50,46 @@ async fn main() -> Result<()> {
         .unwrap().as_str())
         .unwrap();
 
+    backend_config.rate_limit.check()
+        .expect("configuration rate_limit should have positive capacities and refill rates.");
+
     let postgres_pool = persistence::postgres::create_postgres_pool(backend_config.clone().postgres).await
         .expect("configuration postgres should lead to a postgres server.");
 
     let redis_connection = persistence::redis::create_redis_connection(backend_config.clone().redis).await
         .expect("configuration redis should lead to a redis server.");
 
+    let mailer = mail::create_mailer(backend_config.clone().mail)
+        .expect("configuration mail should lead to a usable mail transport.");
+
+    let jwt = match backend_config.auth.mode {
+        AuthMode::Redis => None,
+        AuthMode::Jwt => Some(Arc::new(auth::jwt_entity::create_jwt_keys(backend_config.clone().auth.jwt
+            .expect("configuration auth mode jwt should have an auth.jwt section."))
+            .expect("configuration auth.jwt should lead to usable jwt keys."))),
+    };
+
     let inject = AppInject {
         postgres_pool,
         redis_connection,
         backend_config: backend_config.clone(),
+        mailer,
+        jwt,
     };
 
+    if backend_config.scheduler.enabled {
+        scheduler::spawn(inject.clone(), scheduler::jobs(&inject));
+    }
+
     let router = Router::new()
         .nest("/user", user::user_handler::user_router())
         .nest("/auth", auth::auth_handler::auth_router())
         .nest("/article", article::article_handler::article_router())
-        .route_layer(ServiceBuilder::new()
-            .layer(HandleErrorLayer::new(|err| async move {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Unhandled error: {}", err),
-                )
-            }))
-            .layer(BufferLayer::new(1024))
-            .layer(RateLimitLayer::new(5, Duration::from_secs(1)))
-        )
+        .nest("/category", taxonomy::taxonomy_handler::taxonomy_router::<Categories>())
+        .nest("/tag", taxonomy::taxonomy_handler::taxonomy_router::<Tags>())
+        .nest("/issue", issue::issue_handler::issue_router())
+        .route_layer(middleware::from_fn_with_state(inject.clone(), rate_limit::rate_limit))
+        .layer(middleware::from_fn(request_id::request_id))
         .with_state(inject);
 
     let host = backend_config.clone().host;
@@ -72,6 +99,6 @@ async fn main() -> Result<()> {
     ).await?;
 
     info!("Listening on {}:{}", host.address, host.port);
-    axum::serve(listener, router).await?;
+    axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await?;
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,107 @@
+use std::sync::LazyLock;
+
+use axum::extract::{Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum_extra::headers::Authorization;
+use axum_extra::headers::authorization::Bearer;
+use axum_extra::TypedHeader;
+use redis::aio::MultiplexedConnection;
+use redis::Script;
+use tracing::error;
+
+use crate::AppInject;
+use crate::auth::require_authentication;
+use crate::backend_config::Bucket;
+use crate::client_ip::ClientIp;
+use crate::fenster_error::FensterError;
+use crate::fenster_error::FensterError::TooManyRequests;
+
+const RATE_LIMIT_PREFIX: &str = "rate_limit:";
+
+static TOKEN_BUCKET: LazyLock<Script> = LazyLock::new(|| Script::new(r"
+    local capacity = tonumber(ARGV[1])
+    local rate = tonumber(ARGV[2])
+    local time = redis.call('TIME')
+    local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+    local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'timestamp')
+    local tokens = tonumber(bucket[1]) or capacity
+    local timestamp = tonumber(bucket[2]) or now
+
+    tokens = math.min(capacity, tokens + (now - timestamp) * rate / 1000)
+
+    local retry_after = 0
+    if tokens >= 1 then
+        tokens = tokens - 1
+    else
+        retry_after = math.ceil((1 - tokens) * 1000 / rate)
+    end
+
+    redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'timestamp', now)
+    redis.call('PEXPIRE', KEYS[1], math.ceil(capacity * 1000 / rate))
+
+    return retry_after
+"));
+
+pub async fn rate_limit(
+    State(AppInject { redis_connection, backend_config, jwt, .. }): State<AppInject>,
+    ClientIp(ip): ClientIp,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, FensterError> {
+    let limits = &backend_config.rate_limit;
+
+    let (group, bucket) = if request.uri().path().starts_with("/auth") {
+        ("auth", &limits.auth)
+    } else if request.method() == Method::GET || request.method() == Method::HEAD {
+        ("reads", &limits.reads)
+    } else {
+        ("writes", &limits.writes)
+    };
+
+    let authentication = match bearer {
+        Some(TypedHeader(Authorization(bearer))) => require_authentication(bearer, jwt.as_deref(), redis_connection.clone()).await.ok(),
+        None => None,
+    };
+
+    let client = match &authentication {
+        Some(authentication) => format!("user:{}", authentication.user_id),
+        None => format!("ip:{}", ip),
+    };
+
+    if let Some(authentication) = authentication {
+        request.extensions_mut().insert(authentication);
+    }
+
+    let key = format!("{RATE_LIMIT_PREFIX}{group}:{client}");
+
+    let retry_after = match take_token(&key, bucket, redis_connection).await {
+        Ok(retry_after) => retry_after,
+        Err(err) => {
+            error!("Unable to take rate limit token ({}), letting request through - {}", key, err);
+            0
+        }
+    };
+
+    if retry_after > 0 {
+        let seconds = retry_after.div_ceil(1000);
+
+        return Err(TooManyRequests(
+            format!("Too many requests. Try again in {} seconds.", seconds),
+            seconds,
+        ));
+    }
+
+    Ok(next.run(request).await)
+}
+
+async fn take_token(key: &str, bucket: &Bucket, mut redis: MultiplexedConnection) -> redis::RedisResult<u64> {
+    TOKEN_BUCKET
+        .key(key)
+        .arg(bucket.capacity)
+        .arg(bucket.refill_per_second)
+        .invoke_async(&mut redis).await
+}
\ No newline at end of file
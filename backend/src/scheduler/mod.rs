@@ -0,0 +1,153 @@
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use axum::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::Script;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+
+use crate::AppInject;
+use crate::fenster_error::{error, FensterError, REDIS_ERROR};
+use crate::fenster_error::FensterError::Internal;
+
+pub(crate) mod publish_job;
+
+const LOCK_PREFIX: &str = "scheduler_lock:";
+const LOCK_MILLIS: u64 = 30_000;
+const LOCK_RENEWAL: Duration = Duration::from_millis(LOCK_MILLIS / 3);
+
+static RENEW_LOCK: LazyLock<Script> = LazyLock::new(|| Script::new(r"
+    if redis.call('GET', KEYS[1]) == ARGV[1] then
+        return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    end
+    return 0
+"));
+
+static RELEASE_LOCK: LazyLock<Script> = LazyLock::new(|| Script::new(r"
+    if redis.call('GET', KEYS[1]) == ARGV[1] then
+        return redis.call('DEL', KEYS[1])
+    end
+    return 0
+"));
+
+#[async_trait]
+pub trait Job: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn interval(&self) -> Duration;
+
+    async fn run(&self, inject: &AppInject) -> Result<(), FensterError>;
+}
+
+pub fn jobs(inject: &AppInject) -> Vec<Arc<dyn Job>> {
+    let scheduler = &inject.backend_config.scheduler;
+
+    vec![
+        Arc::new(publish_job::PublishJob::new(Duration::from_secs(scheduler.publish_interval_seconds.max(1)))),
+    ]
+}
+
+pub fn spawn(inject: AppInject, jobs: Vec<Arc<dyn Job>>) {
+    let instance = format!("{:032x}", rand::random::<u128>());
+
+    for job in jobs {
+        let inject = inject.clone();
+        let instance = instance.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(job.interval());
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            info!("Scheduled job ({}) every {} seconds.", job.name(), job.interval().as_secs());
+
+            loop {
+                interval.tick().await;
+
+                match acquire(job.as_ref(), &instance, inject.redis_connection.clone()).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(_) => {
+                        warn!("Skipping run of job ({}) because its lock could not be acquired.", job.name());
+                        continue;
+                    }
+                }
+
+                if let Err(err) = run_locked(job.as_ref(), &instance, &inject).await {
+                    error!("Job ({}) failed. - {:?}", job.name(), err)
+                }
+
+                if let Err(err) = release(job.as_ref(), &instance, inject.redis_connection.clone()).await {
+                    warn!("Unable to release lock of job ({}), it expires on its own. - {:?}", job.name(), err);
+                }
+            }
+        });
+    }
+}
+
+async fn run_locked(job: &dyn Job, instance: &str, inject: &AppInject) -> Result<(), FensterError> {
+    let run = job.run(inject);
+    tokio::pin!(run);
+
+    let mut renewal = tokio::time::interval(LOCK_RENEWAL);
+    renewal.tick().await;
+
+    loop {
+        tokio::select! {
+            result = &mut run => return result,
+            _ = renewal.tick() => {
+                if !renew(job, instance, inject.redis_connection.clone()).await? {
+                    warn!("Lost lock of job ({}), stopping its run.", job.name());
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+async fn acquire(job: &dyn Job, instance: &str, mut redis: MultiplexedConnection) -> Result<bool, FensterError> {
+    let key = lock_key(job);
+
+    let result: Option<String> = redis::cmd("SET")
+        .arg(&key).arg(instance).arg("NX").arg("PX").arg(LOCK_MILLIS)
+        .query_async(&mut redis)
+        .await
+        .inspect_err(|err| {
+            error!("Unable to acquire scheduler lock ({}) - {}", key, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 20)))?;
+
+    Ok(result.is_some())
+}
+
+async fn renew(job: &dyn Job, instance: &str, mut redis: MultiplexedConnection) -> Result<bool, FensterError> {
+    let key = lock_key(job);
+
+    let renewed: i64 = RENEW_LOCK.key(&key).arg(instance).arg(LOCK_MILLIS)
+        .invoke_async(&mut redis)
+        .await
+        .inspect_err(|err| {
+            error!("Unable to renew scheduler lock ({}) - {}", key, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 26)))?;
+
+    Ok(renewed == 1)
+}
+
+async fn release(job: &dyn Job, instance: &str, mut redis: MultiplexedConnection) -> Result<(), FensterError> {
+    let key = lock_key(job);
+
+    RELEASE_LOCK.key(&key).arg(instance)
+        .invoke_async::<_, i64>(&mut redis)
+        .await
+        .inspect_err(|err| {
+            error!("Unable to release scheduler lock ({}) - {}", key, err)
+        })
+        .map_err(|_| Internal(error(REDIS_ERROR, 27)))?;
+
+    Ok(())
+}
+
+fn lock_key(job: &dyn Job) -> String {
+    format!("{LOCK_PREFIX}{}", job.name())
+}
\ No newline at end of file
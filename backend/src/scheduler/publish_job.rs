@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use axum::async_trait;
+use tracing::{info, warn};
+
+use crate::AppInject;
+use crate::article::article_entity;
+use crate::fenster_error::FensterError;
+use crate::fenster_error::FensterError::Conflict;
+use crate::issue::issue_entity;
+use crate::scheduler::Job;
+
+pub struct PublishJob {
+    interval: Duration,
+}
+
+impl PublishJob {
+    pub fn new(interval: Duration) -> Self {
+        PublishJob { interval }
+    }
+}
+
+#[async_trait]
+impl Job for PublishJob {
+    fn name(&self) -> &'static str {
+        "publish"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, AppInject { postgres_pool, .. }: &AppInject) -> Result<(), FensterError> {
+        for number in issue_entity::due(postgres_pool).await? {
+            match issue_entity::publish(number, None, postgres_pool).await {
+                Err(Conflict(message)) => {
+                    warn!("Holding back scheduled issue with issue_number ({}). - {}", number, message);
+                    continue;
+                }
+                result => result?,
+            }
+
+            info!("Published scheduled issue with issue_number ({}).", number);
+        }
+
+        for slug in article_entity::publish_due(postgres_pool).await? {
+            info!("Published scheduled article with article_slug ({}).", slug);
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1 @@
+pub(crate) mod tag_entity;
\ No newline at end of file
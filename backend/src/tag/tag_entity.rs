@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use axum::async_trait;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::error;
+
+use crate::article::article_slug;
+use crate::fenster_error::{error, FensterError, POSTGRES_ERROR};
+use crate::fenster_error::FensterError::{Conflict, Internal, NotFound};
+use crate::taxonomy::{Taxonomy, Term};
+
+pub const TAGS_PER_ARTICLE: usize = 20;
+
+pub type Tag = Term;
+
+pub struct Tags;
+
+pub fn from_names(names: &[String]) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = vec![];
+
+    for name in names {
+        let slug = article_slug::transliterate(name);
+
+        if !slug.is_empty() && !tags.iter().any(|tag| tag.slug == slug) {
+            tags.push(Tag { slug, name: name.trim().to_string() });
+        }
+    }
+
+    tags
+}
+
+#[async_trait]
+impl Taxonomy for Tags {
+    const NAME: &'static str = "Tag";
+
+    async fn store(tag: &Tag, pool: &PgPool) -> Result<(), FensterError> {
+        sqlx::query!(
+            "INSERT INTO fenster.public.tags (tag_slug, tag_name) VALUES ($1, $2)",
+            &tag.slug, &tag.name)
+            .execute(pool)
+            .await
+            .map_err(|err| match err {
+                sqlx::Error::Database(err) if err.is_unique_violation() =>
+                    Conflict(format!("Tag with given slug ({}) already exists.", tag.slug)),
+                err => {
+                    error!("Error while saving tag with tag_slug ({}). - {}", tag.slug, err);
+                    Internal(error(POSTGRES_ERROR, 43))
+                }
+            })?;
+
+        Ok(())
+    }
+
+    async fn exists(slug: &str, pool: &PgPool) -> Result<bool, FensterError> {
+        let result = sqlx::query!(
+            "SELECT EXISTS (SELECT 1 FROM fenster.public.tags WHERE tag_slug=$1) AS \"exists!\"", slug)
+            .fetch_one(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while finding tag with tag_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 44)))?;
+
+        Ok(result.exists)
+    }
+
+    async fn fetch(slug: &str, pool: &PgPool) -> Result<Tag, FensterError> {
+        let result = sqlx::query!(
+            "SELECT tag_slug, tag_name FROM fenster.public.tags WHERE tag_slug=$1", slug)
+            .fetch_optional(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while fetching tag with tag_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 45)))?
+            .ok_or(NotFound(format!("Tag with given slug ({}) does not exist.", slug)))?;
+
+        Ok(Tag {
+            slug: result.tag_slug,
+            name: result.tag_name,
+        })
+    }
+
+    async fn list(pool: &PgPool) -> Result<Vec<Tag>, FensterError> {
+        let rows = sqlx::query!("SELECT tag_slug, tag_name FROM fenster.public.tags ORDER BY tag_name")
+            .fetch_all(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while listing tags. - {}", err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 46)))?;
+
+        Ok(rows.into_iter().map(|row| Tag {
+            slug: row.tag_slug,
+            name: row.tag_name,
+        }).collect())
+    }
+
+    async fn rename(slug: &str, name: &str, pool: &PgPool) -> Result<(), FensterError> {
+        sqlx::query!("UPDATE fenster.public.tags SET tag_name=$2 WHERE tag_slug=$1", slug, name)
+            .execute(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while renaming tag with tag_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 47)))?;
+
+        Ok(())
+    }
+
+    async fn delete(slug: &str, pool: &PgPool) -> Result<(), FensterError> {
+        sqlx::query!("DELETE FROM fenster.public.tags WHERE tag_slug=$1", slug)
+            .execute(pool)
+            .await
+            .inspect_err(|err| {
+                error!("Error while deleting tag with tag_slug ({}). - {}", slug, err)
+            })
+            .map_err(|_| Internal(error(POSTGRES_ERROR, 48)))?;
+
+        Ok(())
+    }
+}
+
+pub async fn for_articles(article_ids: &[i64], pool: &PgPool) -> Result<HashMap<i64, Vec<Tag>>, FensterError> {
+    let rows = sqlx::query!(
+        "SELECT at.article_id, t.tag_slug, t.tag_name FROM fenster.public.article_tags at
+        JOIN fenster.public.tags t ON t.tag_id = at.tag_id
+        WHERE at.article_id = ANY($1) ORDER BY t.tag_name",
+        article_ids)
+        .fetch_all(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching tags of articles ({:?}). - {}", article_ids, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 49)))?;
+
+    let mut tags: HashMap<i64, Vec<Tag>> = HashMap::new();
+
+    for row in rows {
+        tags.entry(row.article_id).or_default().push(Tag {
+            slug: row.tag_slug,
+            name: row.tag_name,
+        });
+    }
+
+    Ok(tags)
+}
+
+pub async fn set_for_article(
+    article_id: i64,
+    tags: &[Tag],
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), FensterError> {
+    let slugs: Vec<String> = tags.iter().map(|tag| tag.slug.clone()).collect();
+    let names: Vec<String> = tags.iter().map(|tag| tag.name.clone()).collect();
+
+    sqlx::query!(
+        "INSERT INTO fenster.public.tags (tag_slug, tag_name)
+        SELECT * FROM UNNEST($1::TEXT[], $2::TEXT[])
+        ON CONFLICT (tag_slug) DO NOTHING",
+        &slugs, &names)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while creating tags ({:?}). - {}", slugs, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 50)))?;
+
+    sqlx::query!("DELETE FROM fenster.public.article_tags WHERE article_id=$1", article_id)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while clearing tags of article with article_id ({}). - {}", article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 51)))?;
+
+    sqlx::query!(
+        "INSERT INTO fenster.public.article_tags (article_id, tag_id)
+        SELECT $1, tag_id FROM fenster.public.tags WHERE tag_slug = ANY($2)",
+        article_id, &slugs)
+        .execute(&mut **transaction)
+        .await
+        .inspect_err(|err| {
+            error!("Error while tagging article with article_id ({}). - {}", article_id, err)
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 52)))?;
+
+    Ok(())
+}
\ No newline at end of file
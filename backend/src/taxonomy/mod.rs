@@ -0,0 +1,68 @@
+use anyhow::Result;
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::article::article_slug;
+use crate::backend_config::BackendConfig;
+use crate::fenster_error::FensterError;
+use crate::validation::{Validate, Validator};
+
+pub(crate) mod taxonomy_handler;
+
+pub const NAME_MAX_LENGTH: usize = 40;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Term {
+    pub(crate) slug: String,
+    pub(crate) name: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTerm {
+    pub(crate) name: String,
+    pub(crate) slug: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EditTerm {
+    pub(crate) name: String,
+}
+
+impl Validate for CreateTerm {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validate_name("name", &self.name, validator);
+
+        if let Some(slug) = &self.slug {
+            validator.field("slug", slug).slug();
+        }
+    }
+}
+
+impl Validate for EditTerm {
+    fn validate(&self, _: &BackendConfig, validator: &mut Validator) {
+        validator.field("name", &self.name).length(1, NAME_MAX_LENGTH);
+    }
+}
+
+pub fn validate_name(field: &'static str, name: &str, validator: &mut Validator) {
+    validator.field(field, name).length(1, NAME_MAX_LENGTH)
+        .require(!article_slug::transliterate(name).is_empty(), "Must contain a letter or digit.");
+}
+
+#[async_trait]
+pub trait Taxonomy: Send + Sync + 'static {
+    const NAME: &'static str;
+
+    async fn store(term: &Term, pool: &PgPool) -> Result<(), FensterError>;
+
+    async fn exists(slug: &str, pool: &PgPool) -> Result<bool, FensterError>;
+
+    async fn fetch(slug: &str, pool: &PgPool) -> Result<Term, FensterError>;
+
+    async fn list(pool: &PgPool) -> Result<Vec<Term>, FensterError>;
+
+    async fn rename(slug: &str, name: &str, pool: &PgPool) -> Result<(), FensterError>;
+
+    async fn delete(slug: &str, pool: &PgPool) -> Result<(), FensterError>;
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+use anyhow::Result;
+use axum::{Json, Router};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+
+use crate::AppInject;
+use crate::article::article_slug;
+use crate::auth::auth_extractor::AuthenticatedUser;
+use crate::fenster_error::FensterError;
+use crate::fenster_error::FensterError::{Conflict, NotFound};
+use crate::taxonomy::{CreateTerm, EditTerm, Taxonomy, Term};
+use crate::user::user_role::Permission;
+use crate::validation::{PathParams, ValidJson};
+
+pub fn taxonomy_router<T: Taxonomy>() -> Router<AppInject> {
+    Router::new()
+        .route("/", get(list_terms::<T>).post(create_term::<T>))
+        .route("/:slug", get(get_term::<T>).patch(edit_term::<T>).delete(delete_term::<T>))
+}
+
+pub async fn list_terms<T: Taxonomy>(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+) -> Result<Json<Vec<Term>>, FensterError> {
+    let terms = T::list(&postgres_pool).await?;
+    Ok(Json(terms))
+}
+
+pub async fn get_term<T: Taxonomy>(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    PathParams(slug): PathParams<String>,
+) -> Result<Json<Term>, FensterError> {
+    let term = T::fetch(&slug, &postgres_pool).await?;
+    Ok(Json(term))
+}
+
+pub async fn create_term<T: Taxonomy>(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    ValidJson(create): ValidJson<CreateTerm>,
+) -> Result<Json<Term>, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    let term = Term {
+        slug: create.slug.unwrap_or_else(|| article_slug::transliterate(&create.name)),
+        name: create.name.trim().to_string(),
+    };
+
+    if T::exists(&term.slug, &postgres_pool).await? {
+        return Err(Conflict(format!("{} with given slug ({}) already exists.", T::NAME, term.slug)));
+    }
+
+    T::store(&term, &postgres_pool).await?;
+    Ok(Json(term))
+}
+
+pub async fn edit_term<T: Taxonomy>(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+    ValidJson(edit): ValidJson<EditTerm>,
+) -> Result<Json<Term>, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    if !T::exists(&slug, &postgres_pool).await? {
+        return Err(NotFound(format!("{} with given slug ({}) does not exist.", T::NAME, slug)));
+    }
+
+    T::rename(&slug, edit.name.trim(), &postgres_pool).await?;
+
+    let term = T::fetch(&slug, &postgres_pool).await?;
+    Ok(Json(term))
+}
+
+pub async fn delete_term<T: Taxonomy>(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user, .. }: AuthenticatedUser,
+    PathParams(slug): PathParams<String>,
+) -> Result<StatusCode, FensterError> {
+    user.require_permission(Permission::ManageArticles)?;
+
+    if !T::exists(&slug, &postgres_pool).await? {
+        return Err(NotFound(format!("{} with given slug ({}) does not exist.", T::NAME, slug)));
+    }
+
+    T::delete(&slug, &postgres_pool).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
\ No newline at end of file
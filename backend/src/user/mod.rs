@@ -1,2 +1,3 @@
 pub(crate) mod user_handler;
-pub(crate) mod user_entity;
\ No newline at end of file
+pub(crate) mod user_entity;
+pub(crate) mod user_role;
\ No newline at end of file
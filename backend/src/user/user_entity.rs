@@ -7,26 +7,65 @@ use sqlx::PgPool;
 use tracing::error;
 
 use crate::fenster_error::{error, FensterError, POSTGRES_ERROR};
-use crate::fenster_error::FensterError::{Internal, NotFound};
+use crate::fenster_error::FensterError::{Forbidden, Internal, NotFound, Unauthorized};
+use crate::user::user_role::{Permission, Role};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct User {
     pub(crate) id: String,
     pub(crate) name: String,
     pub(crate) email: String,
-    pub(crate) author: bool,
+    pub(crate) role: Role,
+    pub(crate) verified: bool,
+    #[serde(skip)]
+    pub(crate) totp_enabled: bool,
+    #[serde(skip)]
+    pub(crate) totp_required: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Author {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) role: Role,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Profile {
+    Private(User),
+    Public(Author),
+}
+
+impl From<&User> for Author {
+    fn from(user: &User) -> Self {
+        Author {
+            id: user.id.clone(),
+            name: user.name.clone(),
+            role: user.role,
+        }
+    }
 }
 
 impl User {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.role.has(permission) && (self.totp_enabled || !self.totp_required)
+    }
+
+    pub fn require_permission(&self, permission: Permission) -> Result<(), FensterError> {
+        if !self.role.has(permission) {
+            return Err(Unauthorized(format!("User with given id ({}) is missing permission ({:?}).", self.id, permission)));
+        }
+
+        if !self.has_permission(permission) {
+            return Err(Forbidden(format!("Role ({:?}) requires two-factor authentication to be enabled.", self.role)));
+        }
+
+        Ok(())
+    }
+
     pub async fn store(&self, password: &str, pool: &PgPool) -> Result<bool, FensterError> {
-        let argon = Argon2::default();
-        let salt = SaltString::generate(&mut OsRng);
-        let hash_string = argon.hash_password(password.as_ref(), &salt)
-            .inspect_err(|err| {
-                error!("Error while creating password hash string. - {}", err);
-            })
-            .map_err(|_| Internal(error(POSTGRES_ERROR, 4)))?
-            .serialize();
+        let hash_string = hash_password(password)?;
 
         let result = sqlx::query!(
             "INSERT INTO fenster.public.users(user_id, user_name, user_email, user_password_hash)
@@ -66,6 +105,18 @@ impl User {
     }
 }
 
+pub fn hash_password(password: &str) -> Result<PasswordHashString, FensterError> {
+    let argon = Argon2::default();
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(argon.hash_password(password.as_ref(), &salt)
+        .inspect_err(|err| {
+            error!("Error while creating password hash string. - {}", err);
+        })
+        .map_err(|_| Internal(error(POSTGRES_ERROR, 79)))?
+        .serialize())
+}
+
 pub async fn exists_id(id: &str, pool: &PgPool) -> Result<bool, FensterError> {
     let result = sqlx::query!(
         "SELECT EXISTS (SELECT user_name FROM fenster.public.users WHERE user_id=$1)", &id)
@@ -102,7 +153,9 @@ pub async fn fetch(id: &str, pool: &PgPool) -> Result<User, FensterError> {
     }
 
     let user = sqlx::query!(
-        "SELECT user_id, user_name, user_email, user_author FROM fenster.public.users WHERE user_id=$1", &id)
+        r#"SELECT user_id, user_name, user_email, user_role AS "user_role: Role", user_verified,
+        user_totp_enabled
+        FROM fenster.public.users WHERE user_id=$1"#, &id)
         .fetch_one(pool)
         .await
         .inspect_err(|err| {
@@ -116,7 +169,10 @@ pub async fn fetch(id: &str, pool: &PgPool) -> Result<User, FensterError> {
         id: user.user_id,
         name: user.user_name,
         email: user.user_email,
-        author: user.user_author.unwrap_or(false),
+        role: user.user_role,
+        verified: user.user_verified,
+        totp_enabled: user.user_totp_enabled,
+        totp_required: false,
     })
 }
 
@@ -135,5 +191,71 @@ pub async fn delete(id: &str, pool: &PgPool) -> Result<(), FensterError> {
             Internal(error(POSTGRES_ERROR, 10))
         })?;
 
+    Ok(())
+}
+
+pub async fn set_role(id: &str, role: Role, pool: &PgPool) -> Result<(), FensterError> {
+    if !exists_id(id, pool).await.unwrap_or(false) {
+        return Err(NotFound("User does not exist.".to_string()));
+    }
+
+    sqlx::query!("UPDATE fenster.public.users SET user_role=$2 WHERE user_id=$1", id, role as Role)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while setting role of user with id ({}). - {}", id, err);
+        })
+        .map_err(|_| {
+            Internal(error(POSTGRES_ERROR, 14))
+        })?;
+
+    Ok(())
+}
+
+pub async fn fetch_by_mail(mail: &str, pool: &PgPool) -> Result<User, FensterError> {
+    let user = sqlx::query!(
+        "SELECT user_id FROM fenster.public.users WHERE user_email=$1", &mail)
+        .fetch_optional(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while fetching user with email ({}). - {}", mail, err);
+        })
+        .map_err(|_| {
+            Internal(error(POSTGRES_ERROR, 15))
+        })?
+        .ok_or(NotFound("User does not exist.".to_string()))?;
+
+    fetch(user.user_id.as_str(), pool).await
+}
+
+pub async fn update_password(id: &str, password: &str, pool: &PgPool) -> Result<(), FensterError> {
+    let hash_string = hash_password(password)?;
+
+    sqlx::query!(
+        "UPDATE fenster.public.users SET user_password_hash=$2 WHERE user_id=$1",
+        id, hash_string.as_str())
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while updating password of user with id ({}). - {}", id, err);
+        })
+        .map_err(|_| {
+            Internal(error(POSTGRES_ERROR, 16))
+        })?;
+
+    Ok(())
+}
+
+pub async fn set_verified(id: &str, pool: &PgPool) -> Result<(), FensterError> {
+    sqlx::query!("UPDATE fenster.public.users SET user_verified=TRUE WHERE user_id=$1", id)
+        .execute(pool)
+        .await
+        .inspect_err(|err| {
+            error!("Error while verifying user with id ({}). - {}", id, err);
+        })
+        .map_err(|_| {
+            Internal(error(POSTGRES_ERROR, 17))
+        })?;
+
     Ok(())
 }
\ No newline at end of file
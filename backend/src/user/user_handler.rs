@@ -1,28 +1,82 @@
 use axum::{Json, Router};
-use axum::extract::{Path, State};
-use axum::routing::get;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{get, put};
+use serde::Deserialize;
 
 use crate::{AppInject, user};
+use crate::auth::auth_extractor::{AuthenticatedUser, MaybeAuthenticatedUser};
+use crate::auth::lockout_entity;
 use crate::fenster_error::FensterError;
-use crate::user::user_entity::User;
+use crate::user::user_entity::{Author, Profile, User};
+use crate::user::user_role::{Permission, Role};
+use crate::validation::{JsonBody, PathParams};
 
 use anyhow::Result;
 
+#[derive(Deserialize)]
+pub struct AssignRole {
+    pub(crate) role: Role,
+}
+
 pub fn user_router() -> Router<AppInject> {
     Router::new()
         .route("/:id", get(get_user))
+        .route("/:id/role", put(assign_role))
+        .route("/:id/unlock", put(unlock_user))
 }
 
 pub async fn get_user(
     State(AppInject { postgres_pool, .. }): State<AppInject>,
-    Path(id): Path<String>,
-) -> Result<Json<User>, FensterError> {
+    MaybeAuthenticatedUser(viewer): MaybeAuthenticatedUser,
+    PathParams(id): PathParams<String>,
+) -> Result<Json<Profile>, FensterError> {
     let exists = user::user_entity::exists_id(&id, &postgres_pool).await?;
 
     if !exists {
         return Err(FensterError::NotFound("User does not exist.".to_string()));
     }
 
+    let user = user::user_entity::fetch(&id, &postgres_pool).await?;
+
+    let private = viewer.is_some_and(|viewer| viewer.id == user.id || viewer.has_permission(Permission::ManageRoles));
+
+    if private {
+        return Ok(Json(Profile::Private(user)));
+    }
+
+    Ok(Json(Profile::Public(Author::from(&user))))
+}
+
+pub async fn assign_role(
+    State(AppInject { postgres_pool, .. }): State<AppInject>,
+    AuthenticatedUser { user: admin, .. }: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+    JsonBody(assign): JsonBody<AssignRole>,
+) -> Result<Json<User>, FensterError> {
+    admin.require_permission(Permission::ManageRoles)?;
+
+    if admin.id == id {
+        return Err(FensterError::Conflict(format!("User with given id ({}) may not change their own role.", id)));
+    }
+
+    user::user_entity::set_role(&id, assign.role, &postgres_pool).await?;
+
     let user = user::user_entity::fetch(&id, &postgres_pool).await?;
     Ok(Json(user))
+}
+
+pub async fn unlock_user(
+    State(AppInject { postgres_pool, redis_connection, .. }): State<AppInject>,
+    AuthenticatedUser { user: admin, .. }: AuthenticatedUser,
+    PathParams(id): PathParams<String>,
+) -> Result<StatusCode, FensterError> {
+    admin.require_permission(Permission::ManageRoles)?;
+
+    if !user::user_entity::exists_id(&id, &postgres_pool).await? {
+        return Err(FensterError::NotFound(format!("User with given id ({}) does not exist.", id)));
+    }
+
+    lockout_entity::unlock(&id, redis_connection).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
\ No newline at end of file
@@ -0,0 +1,349 @@
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use serde::{Deserialize, Deserializer};
+use serde::de::DeserializeOwned;
+
+use crate::AppInject;
+use crate::backend_config::{BackendConfig, PasswordPolicy};
+use crate::fenster_error::{FensterError, FieldError};
+use crate::fenster_error::FensterError::Validation;
+
+const ID_MIN_LENGTH: usize = 3;
+const ID_MAX_LENGTH: usize = 32;
+const SLUG_MAX_LENGTH: usize = 128;
+const PASSWORD_MAX_LENGTH: usize = 256;
+const TOKEN_MAX_LENGTH: usize = 128;
+
+pub trait Validate {
+    fn validate(&self, config: &BackendConfig, validator: &mut Validator);
+}
+
+pub struct ValidJson<T>(pub T);
+
+pub struct ValidForm<T>(pub T);
+
+pub struct JsonBody<T>(pub T);
+
+pub struct QueryParams<T>(pub T);
+
+pub struct PathParams<T>(pub T);
+
+#[async_trait]
+impl<T> FromRequest<AppInject> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = FensterError;
+
+    async fn from_request(request: Request, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let JsonBody(value) = JsonBody::<T>::from_request(request, state).await?;
+        Ok(ValidJson(validated(value, state)?))
+    }
+}
+
+#[async_trait]
+impl<T> FromRequest<AppInject> for ValidForm<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = FensterError;
+
+    async fn from_request(request: Request, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let axum::Form(value) = axum::Form::<T>::from_request(request, state).await
+            .map_err(|rejection| rejected("body", rejection.body_text()))?;
+
+        Ok(ValidForm(validated(value, state)?))
+    }
+}
+
+#[async_trait]
+impl<T> FromRequest<AppInject> for JsonBody<T>
+where
+    T: DeserializeOwned,
+{
+    type Rejection = FensterError;
+
+    async fn from_request(request: Request, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(request, state).await
+            .map_err(|rejection| rejected("body", rejection.body_text()))?;
+
+        Ok(JsonBody(value))
+    }
+}
+
+#[async_trait]
+impl<T> FromRequestParts<AppInject> for QueryParams<T>
+where
+    T: DeserializeOwned,
+{
+    type Rejection = FensterError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) = axum::extract::Query::<T>::from_request_parts(parts, state).await
+            .map_err(|rejection| rejected("query", rejection.body_text()))?;
+
+        Ok(QueryParams(value))
+    }
+}
+
+#[async_trait]
+impl<T> FromRequestParts<AppInject> for PathParams<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Rejection = FensterError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppInject) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(value) = axum::extract::Path::<T>::from_request_parts(parts, state).await
+            .map_err(|rejection| rejected("path", rejection.body_text()))?;
+
+        Ok(PathParams(value))
+    }
+}
+
+fn validated<T: Validate>(value: T, state: &AppInject) -> Result<T, FensterError> {
+    let mut validator = Validator::default();
+    value.validate(&state.backend_config, &mut validator);
+
+    if !validator.errors.is_empty() {
+        return Err(Validation(validator.errors));
+    }
+
+    Ok(value)
+}
+
+fn rejected(field: &str, message: String) -> FensterError {
+    Validation(vec![FieldError {
+        field: field.to_string(),
+        message,
+    }])
+}
+
+pub fn page_offset(page: i64, per_page: i64) -> Result<i64, FensterError> {
+    page.checked_mul(per_page)
+        .ok_or_else(|| rejected("page", "Is too large.".to_string()))
+}
+
+/// Tells a missing field (`None`) apart from an explicit `null` (`Some(None)`), use with `#[serde(default)]`.
+pub fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+pub struct Field<'a> {
+    name: &'static str,
+    value: &'a str,
+    validator: &'a mut Validator,
+    failed: bool,
+}
+
+impl Validator {
+    pub fn field<'a>(&'a mut self, name: &'static str, value: &'a str) -> Field<'a> {
+        Field { name, value, validator: self, failed: false }
+    }
+}
+
+impl Field<'_> {
+    pub fn length(self, min: usize, max: usize) -> Self {
+        let length = self.value.chars().count();
+
+        if length < min {
+            return self.fail(format!("Must be at least {} characters long.", min));
+        }
+
+        if length > max {
+            return self.fail(format!("Must be at most {} characters long.", max));
+        }
+
+        self
+    }
+
+    pub fn id(self) -> Self {
+        self.length(ID_MIN_LENGTH, ID_MAX_LENGTH)
+            .charset(
+                |c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '_' || c == '-',
+                "May only contain lowercase letters, digits, '.', '_' and '-'.",
+            )
+    }
+
+    pub fn slug(self) -> Self {
+        let field = self.length(1, SLUG_MAX_LENGTH)
+            .charset(
+                |c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-',
+                "May only contain lowercase letters, digits and '-'.",
+            );
+
+        if field.value.starts_with('-') || field.value.ends_with('-') || field.value.contains("--") {
+            return field.fail("May not start or end with '-' or contain '--'.".to_string());
+        }
+
+        field
+    }
+
+    pub fn token(self) -> Self {
+        self.length(1, TOKEN_MAX_LENGTH)
+            .charset(|c| c.is_ascii_alphanumeric(), "May only contain letters and digits.")
+    }
+
+    pub fn email(self) -> Self {
+        let field = self.length(3, 254);
+
+        match field.value.split_once('@') {
+            Some((local, domain)) if !local.is_empty() && domain.contains('.') && !domain.contains('@') => field,
+            _ => field.fail("Must be a valid mail address.".to_string()),
+        }
+    }
+
+    pub fn password(self, policy: &PasswordPolicy) -> Self {
+        let field = self.length(policy.min_length, PASSWORD_MAX_LENGTH);
+        let value = field.value;
+
+        if policy.require_lowercase && !value.chars().any(char::is_lowercase) {
+            return field.fail("Must contain a lowercase letter.".to_string());
+        }
+
+        if policy.require_uppercase && !value.chars().any(char::is_uppercase) {
+            return field.fail("Must contain an uppercase letter.".to_string());
+        }
+
+        if policy.require_digit && !value.chars().any(|c| c.is_ascii_digit()) {
+            return field.fail("Must contain a digit.".to_string());
+        }
+
+        if policy.require_symbol && value.chars().all(char::is_alphanumeric) {
+            return field.fail("Must contain a symbol.".to_string());
+        }
+
+        field
+    }
+
+    pub fn require(self, condition: bool, message: &str) -> Self {
+        if condition {
+            return self;
+        }
+
+        self.fail(message.to_string())
+    }
+
+    fn charset(self, allowed: impl Fn(char) -> bool, message: &str) -> Self {
+        if self.value.chars().all(allowed) {
+            return self;
+        }
+
+        self.fail(message.to_string())
+    }
+
+    fn fail(mut self, message: String) -> Self {
+        if !self.failed {
+            self.failed = true;
+            self.validator.errors.push(FieldError { field: self.name.to_string(), message });
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(validate: impl FnOnce(&mut Validator)) -> Vec<String> {
+        let mut validator = Validator::default();
+        validate(&mut validator);
+
+        validator.errors.into_iter().map(|error| error.message).collect()
+    }
+
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+        }
+    }
+
+    #[test]
+    fn password_accepts_matching_policy() {
+        assert!(errors(|v| { v.field("password", "Secret-123").password(&policy()); }).is_empty());
+    }
+
+    #[test]
+    fn password_reports_first_violation_only() {
+        assert_eq!(errors(|v| { v.field("password", "short").password(&policy()); }),
+                   vec!["Must be at least 8 characters long."]);
+        assert_eq!(errors(|v| { v.field("password", "SECRET-123").password(&policy()); }),
+                   vec!["Must contain a lowercase letter."]);
+        assert_eq!(errors(|v| { v.field("password", "secret-123").password(&policy()); }),
+                   vec!["Must contain an uppercase letter."]);
+        assert_eq!(errors(|v| { v.field("password", "Secret-abc").password(&policy()); }),
+                   vec!["Must contain a digit."]);
+        assert_eq!(errors(|v| { v.field("password", "Secret1234").password(&policy()); }),
+                   vec!["Must contain a symbol."]);
+    }
+
+    #[test]
+    fn password_respects_disabled_rules() {
+        let policy = PasswordPolicy {
+            min_length: 4,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+        };
+
+        assert!(errors(|v| { v.field("password", "abcd").password(&policy); }).is_empty());
+    }
+
+    #[test]
+    fn slug_accepts_lowercase_words() {
+        assert!(errors(|v| { v.field("slug", "hello-world-2").slug(); }).is_empty());
+    }
+
+    #[test]
+    fn slug_rejects_invalid_characters_and_dashes() {
+        assert_eq!(errors(|v| { v.field("slug", "Hello World").slug(); }),
+                   vec!["May only contain lowercase letters, digits and '-'."]);
+        assert_eq!(errors(|v| { v.field("slug", "-hello").slug(); }),
+                   vec!["May not start or end with '-' or contain '--'."]);
+        assert_eq!(errors(|v| { v.field("slug", "hello--world").slug(); }),
+                   vec!["May not start or end with '-' or contain '--'."]);
+        assert_eq!(errors(|v| { v.field("slug", "").slug(); }),
+                   vec!["Must be at least 1 characters long."]);
+    }
+
+    #[test]
+    fn email_requires_local_part_and_domain() {
+        assert!(errors(|v| { v.field("email", "user@example.com").email(); }).is_empty());
+
+        for email in ["user.example.com", "@example.com", "user@localhost", "user@exa@mple.com"] {
+            assert_eq!(errors(|v| { v.field("email", email).email(); }), vec!["Must be a valid mail address."]);
+        }
+    }
+
+    #[test]
+    fn page_offset_rejects_overflow() {
+        assert_eq!(page_offset(3, 20).ok(), Some(60));
+        assert!(matches!(page_offset(i64::MAX, 20), Err(Validation(_))));
+    }
+
+    #[test]
+    fn errors_keep_field_names() {
+        let mut validator = Validator::default();
+        validator.field("email", "invalid").email();
+        validator.field("slug", "valid").slug();
+
+        assert_eq!(validator.errors.len(), 1);
+        assert_eq!(validator.errors[0].field, "email");
+    }
+}
\ No newline at end of file